@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::Agent;
+use crate::audit::{Candidate, Method};
+use crate::git::{self, Repo};
+use crate::lock::Lock;
+use crate::paths::PathMatcher;
+use crate::process::ProcessTable;
+use crate::user::xdg_dir;
+
+/// Open spans are rewritten this often, so a watcher killed mid-span loses at most this much.
+const HEARTBEAT_SECS: u64 = 60;
+/// Once the timeline grows past this, spans that ended more than `PRUNE_AGE_SECS` ago are dropped.
+const PRUNE_SIZE: u64 = 1024 * 1024;
+const PRUNE_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// An agent process seen by `aittributor watch` working in a repository, from when it was
+/// first to last sampled there.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Span {
+    /// The agent's co-author email, as in `Agent::email`.
+    pub agent: String,
+    pub repo: PathBuf,
+    pub pid: u32,
+    /// Unix seconds.
+    pub start: u64,
+    pub end: u64,
+}
+
+/// The timeline, `$XDG_STATE_HOME/aittributor/activity.jsonl`.
+pub fn path() -> Option<PathBuf> {
+    Some(
+        xdg_dir("XDG_STATE_HOME", ".local/state")?
+            .join("aittributor")
+            .join("activity.jsonl"),
+    )
+}
+
+/// Read every span. A span is appended again each time it is extended, so only the latest
+/// record of each is kept.
+pub fn read(path: &Path) -> Vec<Span> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let mut spans: Vec<Span> = Vec::new();
+    let mut index: HashMap<_, usize> = HashMap::new();
+    for span in content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Span>(line).ok())
+    {
+        let key = (span.agent.clone(), span.repo.clone(), span.pid, span.start);
+        match index.get(&key) {
+            Some(&i) => {
+                spans[i].end = spans[i].end.max(span.end);
+            }
+            None => {
+                index.insert(key, spans.len());
+                spans.push(span);
+            }
+        }
+    }
+    spans
+}
+
+/// The timeline open for writing. It holds the lock next to the timeline for as long as it
+/// lives, so only one watcher appends to it and prunes it.
+pub struct Timeline {
+    path: PathBuf,
+    _lock: Lock,
+}
+
+impl Timeline {
+    /// Lock the timeline at `path` for writing, or None if another watcher has it.
+    pub fn lock(path: &Path) -> std::io::Result<Option<Timeline>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Lock::try_acquire(&path.with_extension("lock"))?.map(|lock| Timeline {
+            path: path.to_path_buf(),
+            _lock: lock,
+        }))
+    }
+
+    /// Append spans as a single write to a file opened for appending.
+    pub fn append(&self, spans: &[Span], now: u64) -> std::io::Result<()> {
+        if spans.is_empty() {
+            return Ok(());
+        }
+        if fs::metadata(&self.path).is_ok_and(|m| m.len() > PRUNE_SIZE) {
+            self.prune(now.saturating_sub(PRUNE_AGE_SECS))?;
+        }
+        let mut lines = String::new();
+        for span in spans {
+            lines.push_str(&serde_json::to_string(span).map_err(std::io::Error::other)?);
+            lines.push('\n');
+        }
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(lines.as_bytes())
+    }
+
+    fn prune(&self, cutoff: u64) -> std::io::Result<()> {
+        let mut kept = String::new();
+        for span in read(&self.path).into_iter().filter(|s| s.end >= cutoff) {
+            kept.push_str(&serde_json::to_string(&span).map_err(std::io::Error::other)?);
+            kept.push('\n');
+        }
+        let tmp = self.path.with_extension(format!("tmp.{}", std::process::id()));
+        fs::write(&tmp, kept)?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+/// Spans still open in a running watcher, with when each was last written.
+#[derive(Default)]
+pub struct Watcher {
+    open: Vec<(Span, u64)>,
+}
+
+impl Watcher {
+    /// Sample the process table at `now`, returning the spans to write: ones just opened,
+    /// ones due a heartbeat, and ones whose process is gone or has left the repository.
+    pub fn sample(&mut self, table: &ProcessTable, owner: Option<u32>, now: u64) -> Vec<Span> {
+        let mut seen = Vec::new();
+        for process in table.iter() {
+            if (owner.is_some() && process.uid != owner) || !Agent::is_candidate(process) {
+                continue;
+            }
+            if let Some(agent) = Agent::find_for_process(process, false)
+                && let Some(cwd) = crate::agent_cwd(table, process, agent, false)
+                && let Some(repo) = Repo::discover(cwd)
+            {
+                seen.push((agent.email, repo.work_tree, process.pid));
+            }
+        }
+
+        let mut writes = Vec::new();
+        let mut open = Vec::new();
+        for (mut span, written) in std::mem::take(&mut self.open) {
+            let still_seen = seen
+                .iter()
+                .position(|(agent, repo, pid)| span.agent == *agent && span.repo == *repo && span.pid == *pid);
+            match still_seen {
+                Some(i) => {
+                    seen.swap_remove(i);
+                    span.end = now;
+                    if now >= written + HEARTBEAT_SECS {
+                        writes.push(span.clone());
+                        open.push((span, now));
+                    } else {
+                        open.push((span, written));
+                    }
+                }
+                None => writes.push(span),
+            }
+        }
+        for (agent, repo, pid) in seen {
+            let span = Span {
+                agent: agent.to_string(),
+                repo,
+                pid,
+                start: now,
+                end: now,
+            };
+            writes.push(span.clone());
+            open.push((span, now));
+        }
+        self.open = open;
+        writes
+    }
+}
+
+/// The latest span of each agent in the repository that ended at or after `since`, with agents
+/// in timeline order. Spans record the work tree the watcher found, which may be another name
+/// for the repository; those of repositories nested in it don't count.
+pub fn agents_active<'a>(spans: &'a [Span], matcher: &PathMatcher, since: u64) -> Vec<&'a Span> {
+    let in_repo = |span: &Span| {
+        matcher
+            .relative(&span.repo)
+            .is_some_and(|rest| rest.as_os_str().is_empty())
+    };
+    let mut active: Vec<&Span> = Vec::new();
+    for span in spans.iter().filter(|s| s.end >= since && in_repo(s)) {
+        match active.iter_mut().find(|a| a.agent == span.agent) {
+            Some(latest) if span.end > latest.end => *latest = span,
+            Some(_) => {}
+            None => active.push(span),
+        }
+    }
+    active
+}
+
+/// Agents the watcher saw working in the repository since its last commit.
+pub fn detect_agents(matcher: &PathMatcher, debug: bool) -> Vec<Candidate> {
+    let Some(path) = path() else {
+        return Vec::new();
+    };
+    let spans = read(&path);
+    if spans.is_empty() {
+        return Vec::new();
+    }
+    let since = git::head_commit_time().unwrap_or(0);
+    if debug {
+        eprintln!("\n=== Watcher timeline ===");
+        eprintln!("  {} spans in {}, since {}", spans.len(), path.display(), since);
+    }
+    agents_active(&spans, matcher, since)
+        .iter()
+        .filter_map(|span| {
+            let agent = Agent::all().find(|a| a.email == span.agent);
+            if debug && let Some(agent) = agent {
+                eprintln!("  ✓ Watcher saw {} (PID {})", agent.email, span.pid);
+            }
+            agent.map(|agent| Candidate {
+                seen: Some(span.end),
+                ..Candidate::process(agent, Method::Watcher, span.pid)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::Process;
+
+    fn table(repo: &Path, agents: &[(u32, &str)]) -> ProcessTable {
+        let mut processes = vec![Process::loaded(
+            1,
+            None,
+            "init".to_string(),
+            Some(0),
+            Vec::new(),
+            None,
+            Vec::new(),
+        )];
+        for &(pid, name) in agents {
+            processes.push(Process::loaded(
+                pid,
+                Some(1),
+                name.to_string(),
+                Some(1000),
+                vec![name.to_string()],
+                Some(repo.join("src")),
+                Vec::new(),
+            ));
+        }
+        ProcessTable::new(processes, "test")
+    }
+
+    #[test]
+    fn test_watcher_opens_extends_and_closes_spans() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = dir.path().to_path_buf();
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        let mut watcher = Watcher::default();
+
+        let writes = watcher.sample(&table(&repo, &[(10, "claude")]), Some(1000), 100);
+        assert_eq!(writes.len(), 1);
+        assert_eq!(
+            (writes[0].agent.as_str(), writes[0].start),
+            ("Claude Code <noreply@anthropic.com>", 100)
+        );
+        assert_eq!(writes[0].repo, repo);
+
+        // Extended in memory, written again only on heartbeats.
+        assert!(
+            watcher
+                .sample(&table(&repo, &[(10, "claude")]), Some(1000), 105)
+                .is_empty()
+        );
+        let writes = watcher.sample(&table(&repo, &[(10, "claude")]), Some(1000), 160);
+        assert_eq!((writes[0].start, writes[0].end), (100, 160));
+
+        // Another user's agent is ignored; the exited one is closed at its last sighting.
+        let writes = watcher.sample(&table(&repo, &[]), Some(1000), 170);
+        assert_eq!(writes.len(), 1);
+        assert_eq!((writes[0].start, writes[0].end), (100, 160));
+        assert!(watcher.sample(&table(&repo, &[(11, "codex")]), Some(0), 180).is_empty());
+    }
+
+    #[test]
+    fn test_read_keeps_latest_record_of_each_span() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("activity.jsonl");
+        let span = |pid, start, end| Span {
+            agent: "Codex <noreply@openai.com>".to_string(),
+            repo: PathBuf::from("/src/repo"),
+            pid,
+            start,
+            end,
+        };
+        let timeline = Timeline::lock(&path).unwrap().unwrap();
+        assert!(Timeline::lock(&path).unwrap().is_none());
+        timeline.append(&[span(10, 100, 100), span(20, 150, 150)], 150).unwrap();
+        timeline.append(&[span(10, 100, 200)], 200).unwrap();
+        let spans = read(&path);
+        assert_eq!(spans, [span(10, 100, 200), span(20, 150, 150)]);
+
+        let matcher = |root: &str| PathMatcher::new(&[PathBuf::from(root)], &[]);
+        assert_eq!(agents_active(&spans, &matcher("/src/repo"), 180), [&spans[0]]);
+        assert!(agents_active(&spans, &matcher("/src/repo"), 201).is_empty());
+        assert!(agents_active(&spans, &matcher("/src/other"), 0).is_empty());
+        assert!(agents_active(&spans, &matcher("/src"), 0).is_empty());
+
+        // The same repository under a mapped path.
+        let maps = [crate::config::PathMap {
+            from: "/src".to_string(),
+            to: "/home/me/src".to_string(),
+        }];
+        let mapped = PathMatcher::new(&[PathBuf::from("/home/me/src/repo")], &maps);
+        assert!(!agents_active(&spans, &mapped, 180).is_empty());
+    }
+}
@@ -1,39 +1,183 @@
 use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::config::AgentConfig;
+use crate::process::{Process, ProcessTable};
+
+/// XDG base directories an agent may keep its state under.
+#[derive(Clone, Copy, Debug)]
+pub enum Xdg {
+    Config,
+    Data,
+    State,
+}
+
+impl Xdg {
+    pub fn var(self) -> &'static str {
+        match self {
+            Xdg::Config => "XDG_CONFIG_HOME",
+            Xdg::Data => "XDG_DATA_HOME",
+            Xdg::State => "XDG_STATE_HOME",
+        }
+    }
+}
+
+/// How an agent's session files are located under its breadcrumb dir.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BreadcrumbLookup {
+    /// Walk the whole breadcrumb dir.
+    Walk,
+    /// Sessions are grouped in one directory per project, named after the project path with
+    /// separators replaced by '-' (e.g. `~/.claude/projects/-Users-me-src-repo/`).
+    DashedProjectDir,
+    /// One directory per project named by the SHA-256 hex digest of its path
+    /// (e.g. `~/.gemini/tmp/<hash>/`). Any recently modified file inside counts.
+    HashedProjectDir,
+    /// Files in the repository root, checked for recent modification. A trailing '*' matches
+    /// by prefix (e.g. `.aider.tags.cache.*`). No `breadcrumb_dir` is needed.
+    InRepo(&'static [&'static str]),
+    /// Workspace storage of VS Code-family editors (`<app>/User/workspaceStorage/<id>/`), where
+    /// `workspace.json` maps each id to its folder. `apps` are the editors' data dir names.
+    VsCodeWorkspace {
+        apps: &'static [&'static str],
+        activity: WorkspaceActivity,
+    },
+    /// Task history of a Cline-style extension in the editors' global storage, with the
+    /// working directory recorded per task.
+    VsCodeTaskHistory {
+        apps: &'static [&'static str],
+        extension: &'static str,
+    },
+    /// Per-project state of a JetBrains IDE plugin in the IDE's config and cache dirs. Only
+    /// repositories with an `.idea/` dir are checked.
+    JetBrains { plugin_dirs: &'static [&'static str] },
+    /// Chat history a Neovim plugin keeps under Neovim's `stdpath()` dirs.
+    Nvim(NvimHistory),
+}
+
+/// Neovim plugins whose chat history shows the project they were used in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NvimHistory {
+    /// avante.nvim: `stdpath("state")/avante/projects/<project root, '/' as "__">/history/`.
+    Avante,
+    /// codecompanion-history.nvim: `stdpath("data")/codecompanion-history/chats/*.json`, each
+    /// recording the chat's `cwd`.
+    CodeCompanion,
+}
+
+/// Editors that host AI plugins and the helper processes they spawn.
+const EDITORS: &[&str] = &["nvim", "vim", "emacs"];
+
+/// How many parents up a plugin helper may be from its editor (e.g. editor → node → server).
+const MAX_HOST_DEPTH: usize = 3;
+
+/// What counts as recent AI activity inside a matching VS Code workspace storage dir.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WorkspaceActivity {
+    /// Subdirectories holding one file per chat session.
+    Files(&'static [&'static str]),
+    /// `ItemTable` keys (SQL LIKE patterns) in the workspace's `state.vscdb` whose values
+    /// record when each entry was last updated.
+    StateKeys(&'static [&'static str]),
+}
+
+/// Data dir names of the VS Code-family editors an extension may be installed in.
+const VSCODE_APPS: &[&str] = &["Code", "Code - Insiders", "VSCodium", "Cursor", "Windsurf"];
+
+/// A SQLite database in the breadcrumb dir holding session working directories.
+#[derive(Clone, Copy)]
+pub struct SqliteSource {
+    /// Database file name, relative to the breadcrumb dir.
+    pub file: &'static str,
+    /// Query returning one working directory per row, for sessions active since `?1`
+    /// (unix seconds).
+    pub query: &'static str,
+}
 
 pub struct Agent {
+    /// What an `[[agents]]` entry calls this agent to give it an email. Agents without a
+    /// documented co-author address have no built-in `email` and aren't detected until the
+    /// config supplies one.
+    pub name: &'static str,
     pub process_names: &'static [&'static str],
     pub env_vars: &'static [(&'static str, &'static str)],
     pub email: &'static str,
     pub breadcrumb_dir: Option<&'static str>,
     pub breadcrumb_ext: Option<&'static str>,
+    /// Environment variables that relocate the agent's state, in priority order, each with the
+    /// breadcrumb dir relative to it. e.g. `("CODEX_HOME", "sessions")`.
+    pub breadcrumb_env_dirs: &'static [(&'static str, &'static str)],
+    /// XDG base directory and the breadcrumb dir relative to it. Used after `breadcrumb_env_dirs`
+    /// when the XDG variable is set; otherwise `breadcrumb_dir` under `$HOME` applies.
+    pub breadcrumb_xdg_dir: Option<(Xdg, &'static str)>,
+    pub breadcrumb_lookup: BreadcrumbLookup,
+    /// Checked in addition to session files, for agents that moved their sessions to SQLite.
+    pub breadcrumb_sqlite: Option<SqliteSource>,
+    /// Editors a plugin helper process must run under. When set, the helper only counts with
+    /// one of them as a near ancestor, and the editor's cwd is matched against the repo since
+    /// the helper's own cwd is wherever the editor happened to start it.
+    pub host_processes: &'static [&'static str],
     /// When true, process_names must match the basename exactly (not as a substring).
     /// Use for short names like "pi" that would otherwise false-positive on "pipefail" etc.
     pub exact_process_match: bool,
 }
 
+/// Interpreters and launchers whose argv may name an agent, e.g. `node /usr/local/bin/claude`,
+/// `pnpx @openai/codex` or a shell wrapper script.
+const LAUNCHERS: &[&str] = &[
+    "node", "bun", "bunx", "deno", "tsx", "npx", "npm", "pnpm", "pnpx", "yarn", "ruby", "java", "uv", "uvx", "pipx",
+    "env", "sh", "bash", "zsh", "fish", "dash",
+];
+
+/// Agents from the config file, registered once at startup.
+static CONFIGURED_AGENTS: OnceLock<Vec<Agent>> = OnceLock::new();
+
 pub const KNOWN_AGENTS: &[Agent] = &[
     Agent {
         process_names: &["claude"],
         email: "Claude Code <noreply@anthropic.com>",
         breadcrumb_dir: Some(".claude/projects"),
         breadcrumb_ext: Some("jsonl"),
+        breadcrumb_env_dirs: &[("CLAUDE_CONFIG_DIR", "projects")],
+        breadcrumb_lookup: BreadcrumbLookup::DashedProjectDir,
         ..Agent::default()
     },
+    // Older Goose versions write one JSONL file per session whose first line records
+    // "working_dir"; newer ones keep all sessions in sessions.db alongside them.
     Agent {
         process_names: &["goose"],
         email: "Goose <opensource@block.xyz>",
+        breadcrumb_dir: Some(".local/share/goose/sessions"),
+        breadcrumb_ext: Some("jsonl"),
+        breadcrumb_env_dirs: &[("GOOSE_PATH_ROOT", "data/sessions")],
+        breadcrumb_xdg_dir: Some((Xdg::Data, "goose/sessions")),
+        breadcrumb_sqlite: Some(SqliteSource {
+            file: "sessions.db",
+            query: "SELECT working_dir FROM sessions WHERE CAST(strftime('%s', updated_at) AS INTEGER) >= ?1",
+        }),
         ..Agent::default()
     },
     Agent {
         process_names: &["cursor", "cursor-agent"],
         email: "Cursor <cursoragent@cursor.com>",
+        breadcrumb_lookup: BreadcrumbLookup::VsCodeWorkspace {
+            apps: &["Cursor"],
+            activity: WorkspaceActivity::StateKeys(&["composer.composerData"]),
+        },
         ..Agent::default()
     },
     Agent {
         process_names: &["aider"],
         email: "Aider <noreply@aider.chat>",
+        breadcrumb_lookup: BreadcrumbLookup::InRepo(&[
+            ".aider.chat.history.md",
+            ".aider.input.history",
+            ".aider.tags.cache.*",
+        ]),
         ..Agent::default()
     },
+    // Cascade's per-workspace state records no times, so it would only show the editor was
+    // open; Windsurf is found by its process alone.
     Agent {
         process_names: &["windsurf"],
         email: "Windsurf <noreply@codeium.com>",
@@ -44,11 +188,25 @@ pub const KNOWN_AGENTS: &[Agent] = &[
         email: "Codex <noreply@openai.com>",
         breadcrumb_dir: Some(".codex/sessions"),
         breadcrumb_ext: Some("jsonl"),
+        breadcrumb_env_dirs: &[("CODEX_HOME", "sessions")],
         ..Agent::default()
     },
     Agent {
         process_names: &["copilot-agent"],
         email: "GitHub Copilot <noreply@github.com>",
+        // Copilot Chat writes one file per chat and per edit session in the workspace storage.
+        breadcrumb_lookup: BreadcrumbLookup::VsCodeWorkspace {
+            apps: &["Code", "Code - Insiders"],
+            activity: WorkspaceActivity::Files(&["chatSessions", "chatEditingSessions"]),
+        },
+        ..Agent::default()
+    },
+    // The language server spawned by copilot.lua, copilot.vim and copilot.el. The kernel
+    // truncates its process name to "copilot-languag", which would otherwise match Copilot CLI.
+    Agent {
+        process_names: &["copilot-language-server", "copilot-languag"],
+        email: "GitHub Copilot <noreply@github.com>",
+        host_processes: EDITORS,
         ..Agent::default()
     },
     // Copilot CLI is a separate terminal agent from the VS Code extension (copilot-agent above).
@@ -60,6 +218,7 @@ pub const KNOWN_AGENTS: &[Agent] = &[
         // Sessions stored as JSONL event logs in ~/.copilot/session-state/{session-id}/events.jsonl
         breadcrumb_dir: Some(".copilot/session-state"),
         breadcrumb_ext: Some("jsonl"),
+        breadcrumb_xdg_dir: Some((Xdg::Config, ".copilot/session-state")),
         ..Agent::default()
     },
     Agent {
@@ -75,11 +234,19 @@ pub const KNOWN_AGENTS: &[Agent] = &[
     Agent {
         env_vars: &[("CLINE_ACTIVE", "true")],
         email: "Cline <noreply@cline.bot>",
+        breadcrumb_lookup: BreadcrumbLookup::VsCodeTaskHistory {
+            apps: VSCODE_APPS,
+            extension: "saoudrizwan.claude-dev",
+        },
         ..Agent::default()
     },
     Agent {
         process_names: &["gemini"],
         email: "Gemini CLI Agent <gemini-cli-agent@google.com>",
+        // Logs, chats and checkpoints live in ~/.gemini/tmp/<sha256 of project root>/
+        breadcrumb_dir: Some(".gemini/tmp"),
+        breadcrumb_env_dirs: &[("GEMINI_CLI_HOME", ".gemini/tmp")],
+        breadcrumb_lookup: BreadcrumbLookup::HashedProjectDir,
         ..Agent::default()
     },
     Agent {
@@ -87,9 +254,137 @@ pub const KNOWN_AGENTS: &[Agent] = &[
         email: "Pi <noreply@pi.dev>",
         breadcrumb_dir: Some(".pi/agent/sessions"),
         breadcrumb_ext: Some("jsonl"),
+        breadcrumb_env_dirs: &[("PI_CODING_AGENT_DIR", "sessions")],
+        exact_process_match: true,
+        ..Agent::default()
+    },
+    // Qwen Code is a fork of Gemini CLI and keeps the same per-project layout.
+    Agent {
+        process_names: &["qwen"],
+        email: "Qwen-Coder <qwen-coder@alibabacloud.com>",
+        breadcrumb_dir: Some(".qwen/tmp"),
+        breadcrumb_lookup: BreadcrumbLookup::HashedProjectDir,
+        ..Agent::default()
+    },
+    // Crush keeps its session database in .crush/ in the project.
+    Agent {
+        process_names: &["crush"],
+        email: "Crush <crush@charm.land>",
+        breadcrumb_lookup: BreadcrumbLookup::InRepo(&[".crush"]),
+        ..Agent::default()
+    },
+    Agent {
+        name: "kiro",
+        process_names: &["kiro"],
+        ..Agent::default()
+    },
+    // "droid" would otherwise match android tooling.
+    Agent {
+        process_names: &["droid"],
+        email: "factory-droid[bot] <138933559+factory-droid[bot]@users.noreply.github.com>",
+        breadcrumb_dir: Some(".factory/sessions"),
+        breadcrumb_ext: Some("jsonl"),
+        exact_process_match: true,
+        ..Agent::default()
+    },
+    Agent {
+        name: "auggie",
+        process_names: &["auggie"],
+        ..Agent::default()
+    },
+    // Roo Code and Kilo Code are Cline forks with the same task history layout.
+    Agent {
+        email: "Roo Code <noreply@roocode.com>",
+        breadcrumb_lookup: BreadcrumbLookup::VsCodeTaskHistory {
+            apps: VSCODE_APPS,
+            extension: "rooveterinaryinc.roo-cline",
+        },
+        ..Agent::default()
+    },
+    Agent {
+        process_names: &["kilocode"],
+        email: "Kilo Code <noreply@kilocode.ai>",
+        breadcrumb_lookup: BreadcrumbLookup::VsCodeTaskHistory {
+            apps: VSCODE_APPS,
+            extension: "kilocode.kilo-code",
+        },
+        ..Agent::default()
+    },
+    // The Continue CLI binary is `cn`, far too short to match as a substring.
+    Agent {
+        name: "continue",
+        process_names: &["cn"],
         exact_process_match: true,
         ..Agent::default()
     },
+    // Warp's agent mode runs inside the terminal itself; each query is logged with its working
+    // directory. On macOS the database lives in Warp's group container instead.
+    Agent {
+        email: "Warp <agent@warp.dev>",
+        breadcrumb_dir: Some(".local/state/warp-terminal"),
+        breadcrumb_xdg_dir: Some((Xdg::State, "warp-terminal")),
+        breadcrumb_sqlite: Some(SqliteSource {
+            file: "warp.sqlite",
+            query: "SELECT working_directory FROM ai_queries WHERE CAST(strftime('%s', start_ts) AS INTEGER) >= ?1",
+        }),
+        ..Agent::default()
+    },
+    // Zed's own agent runs in the editor process, so a running editor says nothing. Its threads
+    // are kept in threads.db with the workspace folders they were started in, newline-separated;
+    // the first is the project. External agents it drives over ACP are matched by their own
+    // names. On macOS the database lives under Application Support instead.
+    Agent {
+        email: "Zed <noreply@zed.dev>",
+        breadcrumb_dir: Some(".local/share/zed/threads"),
+        breadcrumb_xdg_dir: Some((Xdg::Data, "zed/threads")),
+        breadcrumb_sqlite: Some(SqliteSource {
+            file: "threads.db",
+            query: "SELECT substr(folder_paths, 1, instr(folder_paths || char(10), char(10)) - 1) FROM threads \
+                    WHERE folder_paths IS NOT NULL AND CAST(strftime('%s', updated_at) AS INTEGER) >= ?1",
+        }),
+        ..Agent::default()
+    },
+    Agent {
+        process_names: &["devin"],
+        email: "Devin AI <158243242+devin-ai-integration[bot]@users.noreply.github.com>",
+        ..Agent::default()
+    },
+    Agent {
+        process_names: &["openhands"],
+        email: "openhands <openhands@all-hands.dev>",
+        ..Agent::default()
+    },
+    // AI Assistant runs inside the IDE's JVM; its chats are kept per project in the IDE dirs.
+    // Its rules in .aiassistant/ are committed, so their mtime says nothing about a session.
+    Agent {
+        name: "jetbrains-ai-assistant",
+        breadcrumb_lookup: BreadcrumbLookup::JetBrains {
+            plugin_dirs: &["aia", "ai-assistant"],
+        },
+        ..Agent::default()
+    },
+    // Junie runs as an IDE plugin, and also as a standalone `junie` CLI. Its .junie/ dir in the
+    // project is committed guidelines, so only the IDE's per-project state counts.
+    Agent {
+        name: "junie",
+        process_names: &["junie"],
+        breadcrumb_lookup: BreadcrumbLookup::JetBrains {
+            plugin_dirs: &["junie"],
+        },
+        ..Agent::default()
+    },
+    // Neovim chat plugins run inside nvim and talk to model APIs directly, so they're only
+    // found through the history they persist.
+    Agent {
+        name: "avante",
+        breadcrumb_lookup: BreadcrumbLookup::Nvim(NvimHistory::Avante),
+        ..Agent::default()
+    },
+    Agent {
+        name: "codecompanion",
+        breadcrumb_lookup: BreadcrumbLookup::Nvim(NvimHistory::CodeCompanion),
+        ..Agent::default()
+    },
     // TODO: OpenCode sessions are stored in SQLite (~/.local/share/opencode/opencode.db),
     // not flat files. Breadcrumb scanning would require a new SQLite-based strategy.
     Agent {
@@ -102,15 +397,91 @@ pub const KNOWN_AGENTS: &[Agent] = &[
 impl Agent {
     const fn default() -> Self {
         Agent {
+            name: "",
             process_names: &[],
             env_vars: &[],
             email: "",
             breadcrumb_dir: None,
             breadcrumb_ext: None,
+            breadcrumb_env_dirs: &[],
+            breadcrumb_xdg_dir: None,
+            breadcrumb_lookup: BreadcrumbLookup::Walk,
+            breadcrumb_sqlite: None,
+            host_processes: &[],
             exact_process_match: false,
         }
     }
 
+    /// An agent defined in the config. An entry naming a built-in agent keeps its detection,
+    /// adding to it, and gives it the entry's email.
+    pub fn from_config(c: &AgentConfig) -> Agent {
+        fn leak(s: &str) -> &'static str {
+            Box::leak(s.into())
+        }
+        let base = c
+            .name
+            .as_deref()
+            .and_then(|name| KNOWN_AGENTS.iter().find(|a| !a.name.is_empty() && a.name == name));
+        if let (Some(name), None) = (&c.name, base) {
+            eprintln!("aittributor: no built-in agent is named {:?}, ignoring its name", name);
+        }
+        let base = base.unwrap_or(&const { Agent::default() });
+        let process_names: Vec<&'static str> = base
+            .process_names
+            .iter()
+            .copied()
+            .chain(c.process_names.iter().map(|n| leak(&n.to_lowercase())))
+            .collect();
+        let env_vars: Vec<(&'static str, &'static str)> = base
+            .env_vars
+            .iter()
+            .copied()
+            .chain(c.env.iter().map(|(k, v)| (leak(k), leak(v))))
+            .collect();
+        Agent {
+            process_names: Box::leak(process_names.into()),
+            env_vars: Box::leak(env_vars.into()),
+            email: leak(&c.email),
+            breadcrumb_dir: c.breadcrumb_dir.as_deref().map(leak).or(base.breadcrumb_dir),
+            breadcrumb_ext: c.breadcrumb_ext.as_deref().map(leak).or(base.breadcrumb_ext),
+            exact_process_match: c.exact_process_match || base.exact_process_match,
+            ..*base
+        }
+    }
+
+    /// Register the agents defined in the config. Their strings are leaked, since agents live
+    /// for the whole run like the built-in ones. Only the first call has any effect.
+    pub fn register(configured: &[AgentConfig]) {
+        let _ = CONFIGURED_AGENTS.set(configured.iter().map(Agent::from_config).collect());
+    }
+
+    /// Configured agents followed by the built-in ones, so a config entry can take over a name.
+    /// Built-in agents without an email are left out.
+    pub fn all() -> impl Iterator<Item = &'static Agent> {
+        CONFIGURED_AGENTS
+            .get()
+            .into_iter()
+            .flatten()
+            .chain(KNOWN_AGENTS.iter().filter(|a| !a.email.is_empty()))
+    }
+
+    /// Built-in agents waiting for a config entry to give them an email.
+    pub fn without_email() -> impl Iterator<Item = &'static Agent> {
+        KNOWN_AGENTS.iter().filter(|known| {
+            known.email.is_empty()
+                && !CONFIGURED_AGENTS
+                    .get()
+                    .into_iter()
+                    .flatten()
+                    .any(|c| c.name == known.name)
+        })
+    }
+
+    /// Whether this agent was defined in the config file.
+    pub fn is_configured(&self) -> bool {
+        !KNOWN_AGENTS.iter().any(|known| std::ptr::eq(known, self))
+    }
+
     /// Extract the bare email address from a "Name <addr>" string.
     /// e.g. "Claude Code <noreply@anthropic.com>" → "noreply@anthropic.com"
     pub fn extract_email_addr(email: &str) -> &str {
@@ -126,7 +497,7 @@ impl Agent {
         let basename = path.file_name().and_then(|n| n.to_str()).unwrap_or(name);
         let basename_lower = basename.to_lowercase();
 
-        KNOWN_AGENTS.iter().find(|agent| {
+        Self::all().find(|agent| {
             !agent.process_names.is_empty()
                 && agent.process_names.iter().any(|&pn| {
                     if agent.exact_process_match {
@@ -138,8 +509,15 @@ impl Agent {
         })
     }
 
+    /// Find an agent by process name, or by a case-insensitive part of its email, as users
+    /// name them on the command line.
+    pub fn find_by_name_or_email(name: &str) -> Option<&'static Agent> {
+        let name_lower = name.to_lowercase();
+        Self::find_by_name(name).or_else(|| Self::all().find(|a| a.email.to_lowercase().contains(&name_lower)))
+    }
+
     pub fn find_by_env() -> Option<&'static Agent> {
-        KNOWN_AGENTS.iter().find(|agent| {
+        Self::all().find(|agent| {
             !agent.env_vars.is_empty()
                 && agent
                     .env_vars
@@ -147,13 +525,42 @@ impl Agent {
                     .all(|(key, value)| std::env::var(key).ok().as_deref() == Some(*value))
         })
     }
+    /// Whether a process is worth loading argv and cwd for. Only looks at the process name,
+    /// which is the one field the process table reads eagerly. Names of exactly 15 bytes may
+    /// be truncated by the kernel, so they're always considered.
+    pub fn is_candidate(process: &Process) -> bool {
+        let name = process.name.to_lowercase();
+        name.len() >= 15
+            || Self::find_by_name(&name).is_some()
+            || LAUNCHERS.iter().any(|&l| name == l)
+            || name.starts_with("python")
+    }
+
+    /// The editor hosting a plugin helper process: the nearest of its first few ancestors named
+    /// in `host_processes` (exactly, or followed by '-' as in `emacs-29.4`).
+    pub fn host_process<'a>(&self, table: &'a ProcessTable, process: &Process) -> Option<&'a Process> {
+        let mut parent = process.parent;
+        for _ in 0..MAX_HOST_DEPTH {
+            let host = table.process(parent?)?;
+            let name = host.name.to_lowercase();
+            if self
+                .host_processes
+                .iter()
+                .any(|&editor| name == editor || name.strip_prefix(editor).is_some_and(|rest| rest.starts_with('-')))
+            {
+                return Some(host);
+            }
+            parent = host.parent.filter(|&pid| pid != host.pid);
+        }
+        None
+    }
 
-    pub fn find_for_process(process: &sysinfo::Process, debug: bool) -> Option<&'static Agent> {
-        let name = process.name().to_string_lossy();
+    pub fn find_for_process(process: &Process, debug: bool) -> Option<&'static Agent> {
+        let name = &process.name;
         if debug {
             eprintln!("      Checking process name: {}", name);
         }
-        if let Some(agent) = Self::find_by_name(&name) {
+        if let Some(agent) = Self::find_by_name(name) {
             if debug {
                 eprintln!("        ✓ Matched agent: {}", agent.email);
             }
@@ -162,11 +569,10 @@ impl Agent {
 
         // Check basename(argv[0])
         if let Some(arg0) = process.cmd().first() {
-            let arg0_str = arg0.to_string_lossy();
             if debug {
-                eprintln!("      Checking basename(argv[0]): {}", arg0_str);
+                eprintln!("      Checking basename(argv[0]): {}", arg0);
             }
-            if let Some(agent) = Self::find_by_name(&arg0_str) {
+            if let Some(agent) = Self::find_by_name(arg0) {
                 if debug {
                     eprintln!("        ✓ Matched agent: {}", agent.email);
                 }
@@ -175,15 +581,11 @@ impl Agent {
         }
 
         // Check first basename(argv[1:]) that doesn't start with '-'
-        if let Some(arg) = process.cmd().iter().skip(1).find(|arg| {
-            let arg_str = arg.to_string_lossy();
-            !arg_str.starts_with('-')
-        }) {
-            let arg_str = arg.to_string_lossy();
+        if let Some(arg) = process.cmd().iter().skip(1).find(|arg| !arg.starts_with('-')) {
             if debug {
-                eprintln!("      Checking first non-flag arg from argv[1:]: {}", arg_str);
+                eprintln!("      Checking first non-flag arg from argv[1:]: {}", arg);
             }
-            if let Some(agent) = Self::find_by_name(&arg_str) {
+            if let Some(agent) = Self::find_by_name(arg) {
                 if debug {
                     eprintln!("        ✓ Matched agent: {}", agent.email);
                 }
@@ -0,0 +1,213 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::Agent;
+use crate::user::xdg_dir;
+
+/// The log is rotated to `audit.jsonl.1` once it grows past this, keeping one old file.
+const ROTATE_SIZE: u64 = 1024 * 1024;
+
+/// How an agent was found.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Method {
+    /// Its signature environment variables are set in the hook.
+    Env,
+    /// It is an ancestor of the hook, so it is making the commit.
+    Ancestry,
+    /// It is running alongside the commit with its cwd in the repository.
+    ProcessTree,
+    /// It reported editing staged files with `aittributor mark`.
+    Ledger,
+    /// `aittributor watch` saw it working in the repository.
+    Watcher,
+    /// Its session files show recent activity in the repository.
+    Breadcrumb,
+}
+
+impl Method {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Method::Env => "env",
+            Method::Ancestry => "ancestry",
+            Method::ProcessTree => "process tree",
+            Method::Ledger => "ledger",
+            Method::Watcher => "watcher",
+            Method::Breadcrumb => "breadcrumb",
+        }
+    }
+}
+
+/// An agent found by one detection method, with the process or file it was found through.
+#[derive(Clone)]
+pub struct Candidate {
+    pub agent: &'static Agent,
+    pub method: Method,
+    pub pid: Option<u32>,
+    pub breadcrumb: Option<PathBuf>,
+    /// When the evidence was last updated (the breadcrumb's mtime, the latest mark, or when
+    /// the watcher last saw the agent), in unix seconds. None for running processes.
+    pub seen: Option<u64>,
+}
+
+impl Candidate {
+    pub fn new(agent: &'static Agent, method: Method) -> Self {
+        Candidate {
+            agent,
+            method,
+            pid: None,
+            breadcrumb: None,
+            seen: None,
+        }
+    }
+
+    pub fn process(agent: &'static Agent, method: Method, pid: u32) -> Self {
+        Candidate {
+            pid: Some(pid),
+            ..Self::new(agent, method)
+        }
+    }
+}
+
+/// A candidate as logged.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct Evidence {
+    pub agent: String,
+    pub method: Method,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breadcrumb: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seen: Option<u64>,
+}
+
+impl From<&Candidate> for Evidence {
+    fn from(candidate: &Candidate) -> Self {
+        Evidence {
+            agent: candidate.agent.email.to_string(),
+            method: candidate.method,
+            pid: candidate.pid,
+            breadcrumb: candidate.breadcrumb.clone(),
+            seen: candidate.seen,
+        }
+    }
+}
+
+/// One hook run: what was found, and what was written.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Record {
+    /// Unix seconds.
+    pub timestamp: u64,
+    pub repo: PathBuf,
+    /// As passed by git: message, template, merge, squash, commit, or empty.
+    #[serde(default)]
+    pub commit_source: String,
+    /// The config rule that applied, numbered from 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<usize>,
+    /// Attribution was turned off by the rule.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
+    /// Every agent found, once per method that found it.
+    #[serde(default)]
+    pub candidates: Vec<Evidence>,
+    /// Agents whose trailers were added, after deduplication and any prompt.
+    #[serde(default)]
+    pub trailers: Vec<String>,
+    /// Detection ran out of time, so nothing was added.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+}
+
+/// The log, `$XDG_STATE_HOME/aittributor/audit.jsonl`.
+pub fn path() -> Option<PathBuf> {
+    Some(
+        xdg_dir("XDG_STATE_HOME", ".local/state")?
+            .join("aittributor")
+            .join("audit.jsonl"),
+    )
+}
+
+fn rotated(path: &Path) -> PathBuf {
+    path.with_extension("jsonl.1")
+}
+
+pub fn append(path: &Path, record: &Record) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::metadata(path).is_ok_and(|m| m.len() > ROTATE_SIZE) {
+        fs::rename(path, rotated(path))?;
+    }
+    let mut line = serde_json::to_string(record).map_err(std::io::Error::other)?;
+    line.push('\n');
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+/// Every record, oldest first, including the rotated file.
+pub fn read(path: &Path) -> Vec<Record> {
+    [rotated(path), path.to_path_buf()]
+        .iter()
+        .filter_map(|p| fs::read_to_string(p).ok())
+        .flat_map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect::<Vec<Record>>()
+        })
+        .collect()
+}
+
+/// Log a hook run. Failing to log never affects the commit.
+pub fn log(record: &Record, debug: bool) {
+    let Some(path) = path() else {
+        return;
+    };
+    if let Err(e) = append(&path, record)
+        && debug
+    {
+        eprintln!("aittributor: failed to write {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_includes_rotated_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let record = |timestamp| Record {
+            timestamp,
+            repo: PathBuf::from("/src/repo"),
+            candidates: vec![Evidence {
+                agent: "Codex <noreply@openai.com>".to_string(),
+                method: Method::Breadcrumb,
+                pid: None,
+                breadcrumb: Some(PathBuf::from("/home/me/.codex/sessions/a.jsonl")),
+                seen: Some(timestamp),
+            }],
+            trailers: vec!["Codex <noreply@openai.com>".to_string()],
+            ..Record::default()
+        };
+        append(&path, &record(1)).unwrap();
+        fs::rename(&path, rotated(&path)).unwrap();
+        append(&path, &record(2)).unwrap();
+
+        let records = read(&path);
+        assert_eq!(records, [record(1), record(2)]);
+
+        let line = fs::read_to_string(&path).unwrap();
+        assert!(line.contains(r#""method":"breadcrumb""#));
+        assert!(!line.contains("timed_out") && !line.contains("pid"));
+    }
+}
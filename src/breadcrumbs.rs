@@ -1,18 +1,45 @@
+use std::cell::RefCell;
 use std::fs;
 use std::io::BufRead;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
-use crate::agent::{Agent, KNOWN_AGENTS};
+use crate::agent::{Agent, BreadcrumbLookup, NvimHistory};
+use crate::cache::SessionCache;
+use crate::config::Config;
+use crate::paths::{self, PathMatcher};
+use crate::user::{self, home_dir};
+
+mod jetbrains;
+mod nvim;
+mod vscode;
 
 const CUTOFF_SECS: u64 = 2 * 60 * 60; // 2 hours as a rough approximation
 
 /// Maximum number of lines to read from a session file when looking for "cwd".
 const MAX_LINES_TO_SCAN: usize = 5;
 
-fn home_dir() -> Option<String> {
-    std::env::var("HOME").ok()
-}
+/// JSON keys session files use to record the working directory.
+const CWD_KEYS: &[&str] = &["cwd", "working_dir"];
+
+/// Claude shortens project dir names beyond this length and appends a hash, so longer
+/// names can't be predicted from the repo path.
+const MAX_DASHED_DIR_LEN: usize = 200;
+
+/// Limits on the repo subdirectories hashed when looking for per-project dirs, so a huge
+/// monorepo can't blow the time budget.
+const MAX_HASHED_DIR_DEPTH: usize = 2;
+const MAX_HASHED_DIRS: usize = 500;
+
+/// Subdirectories never used as a project root.
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "vendor", "dist", "build"];
+
+/// How much of the end of an aider history file to search for the last session header.
+const HISTORY_TAIL_BYTES: u64 = 64 * 1024;
+
+/// In-repo history files only count if their last session started within this window.
+/// Sessions can run for hours after starting, so this is much wider than CUTOFF_SECS.
+const MAX_SESSION_AGE_SECS: u64 = 24 * 60 * 60;
 
 fn is_recent(path: &Path, cutoff: SystemTime) -> bool {
     path.metadata()
@@ -25,25 +52,23 @@ fn has_extension(path: &Path, ext: &str) -> bool {
 }
 
 fn extract_cwd_from_json(line: &str) -> Option<&str> {
-    // Simple string extraction: find "cwd":"<value>"
-    let marker = "\"cwd\":\"";
-    let start = line.find(marker)? + marker.len();
-    let rest = &line[start..];
-    let end = rest.find('"')?;
-    Some(&rest[..end])
+    // Simple string extraction: find "cwd":"<value>" (or Goose's "working_dir")
+    CWD_KEYS.iter().find_map(|key| {
+        let marker = format!("\"{}\":\"", key);
+        let start = line.find(&marker)? + marker.len();
+        let rest = &line[start..];
+        let end = rest.find('"')?;
+        Some(&rest[..end])
+    })
 }
 
-fn cwd_matches_repo(cwd: &str, repo_path: &Path) -> bool {
-    Path::new(cwd).starts_with(repo_path)
+fn cwd_matches_repo(cwd: &str, matcher: &PathMatcher) -> bool {
+    matcher.matches(Path::new(cwd))
 }
 
-/// Read the first few lines of a file looking for a "cwd" field that
-/// matches the repo path. Returns true on match.
-fn file_has_matching_cwd(path: &Path, repo_path: &Path, debug: bool) -> bool {
-    let file = match fs::File::open(path) {
-        Ok(f) => f,
-        Err(_) => return false,
-    };
+/// Read the first few lines of a session file looking for a "cwd" field.
+fn read_session_cwd(path: &Path, debug: bool) -> Option<String> {
+    let file = fs::File::open(path).ok()?;
     let reader = std::io::BufReader::new(file);
 
     for line in reader.lines().take(MAX_LINES_TO_SCAN) {
@@ -55,17 +80,52 @@ fn file_has_matching_cwd(path: &Path, repo_path: &Path, debug: bool) -> bool {
             if debug {
                 eprintln!("    {} cwd: {}", path.display(), cwd);
             }
-            return cwd_matches_repo(cwd, repo_path);
+            return Some(cwd.to_string());
         }
     }
 
-    false
+    None
+}
+
+/// State shared by every breadcrumb lookup in one run.
+struct Scan<'a> {
+    repo_path: &'a Path,
+    /// Matches cwds found in breadcrumbs against the repository.
+    matcher: PathMatcher,
+    cutoff: SystemTime,
+    /// When set, files belonging to other users are ignored.
+    owner: Option<u32>,
+    cache: SessionCache,
+    debug: bool,
+    /// The file or dir the last match was found in.
+    evidence: RefCell<Option<PathBuf>>,
+}
+
+impl<'a> Scan<'a> {
+    fn new(repo_path: &'a Path, config: &Config, debug: bool) -> Self {
+        Scan {
+            repo_path,
+            matcher: PathMatcher::new(&[repo_path.to_path_buf()], &config.path_map),
+            cutoff: SystemTime::now() - std::time::Duration::from_secs(CUTOFF_SECS),
+            owner: (!config.include_other_users).then(user::current_uid),
+            cache: SessionCache::default_path().map(SessionCache::load).unwrap_or_default(),
+            debug,
+            evidence: RefCell::new(None),
+        }
+    }
+
+    /// Note where a match was found. Returns true, so lookups can end with `scan.found(path)`.
+    fn found(&self, path: &Path) -> bool {
+        *self.evidence.borrow_mut() = Some(path.to_path_buf());
+        true
+    }
 }
 
 /// Walk nested subdirectories (any depth) looking for recent files whose
 /// first few lines contain a "cwd" field matching the repo path.
-fn find_session_file_with_cwd(dir: &Path, ext: &str, repo_path: &Path, cutoff: SystemTime, debug: bool) -> bool {
+fn find_session_file_with_cwd(scan: &mut Scan, agent: &Agent, dir: &Path, ext: &str) -> bool {
     let mut dirs_to_visit = vec![dir.to_path_buf()];
+    let addr = Agent::extract_email_addr(agent.email);
 
     while let Some(current) = dirs_to_visit.pop() {
         let entries = match fs::read_dir(&current) {
@@ -78,11 +138,19 @@ fn find_session_file_with_cwd(dir: &Path, ext: &str, repo_path: &Path, cutoff: S
                 dirs_to_visit.push(path);
                 continue;
             }
-            if !has_extension(&path, ext) || !is_recent(&path, cutoff) {
+            if !has_extension(&path, ext) || !is_recent(&path, scan.cutoff) {
+                continue;
+            }
+            if scan.owner.is_some() && user::owner_of(&path) != scan.owner {
+                if scan.debug {
+                    eprintln!("    {} belongs to another user, skipping", path.display());
+                }
                 continue;
             }
-            if file_has_matching_cwd(&path, repo_path, debug) {
-                return true;
+            let debug = scan.debug;
+            let cwd = scan.cache.cwd(&path, addr, || read_session_cwd(&path, debug));
+            if cwd.is_some_and(|cwd| cwd_matches_repo(&cwd, &scan.matcher)) {
+                return scan.found(&path);
             }
         }
     }
@@ -90,18 +158,232 @@ fn find_session_file_with_cwd(dir: &Path, ext: &str, repo_path: &Path, cutoff: S
     false
 }
 
-fn check_source(agent: &'static Agent, repo_path: &Path, cutoff: SystemTime, debug: bool) -> bool {
-    let breadcrumb_dir = match agent.breadcrumb_dir {
-        Some(d) => d,
-        None => return false,
+/// Resolve where an agent keeps its breadcrumbs: the first override variable that is set, then
+/// its XDG base directory if that variable is set, then `breadcrumb_dir` under the home directory.
+fn resolve_breadcrumb_dir(agent: &Agent, home: Option<&Path>, env: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    let breadcrumb_dir = agent.breadcrumb_dir?;
+    let env = |var: &str| env(var).filter(|v| !v.is_empty());
+
+    for (var, rel) in agent.breadcrumb_env_dirs {
+        if let Some(dir) = env(var) {
+            return Some(Path::new(&dir).join(rel));
+        }
+    }
+    if let Some((xdg, rel)) = agent.breadcrumb_xdg_dir
+        && let Some(dir) = env(xdg.var())
+    {
+        return Some(Path::new(&dir).join(rel));
+    }
+    Some(home?.join(breadcrumb_dir))
+}
+
+pub fn breadcrumb_dir(agent: &Agent) -> Option<PathBuf> {
+    resolve_breadcrumb_dir(agent, home_dir().as_deref(), |var| std::env::var(var).ok())
+}
+
+/// Possible project dir names for a path. Claude currently replaces every non-alphanumeric
+/// character with '-', while older versions only replaced '/' and '.'; try all of them.
+fn dashed_dir_names(path: &Path) -> Option<Vec<String>> {
+    let path = path.to_str()?;
+    if !path.is_ascii() {
+        return None;
+    }
+    let mut names = vec![
+        path.replace('/', "-"),
+        path.replace(['/', '.'], "-"),
+        path.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect(),
+    ];
+    names.dedup();
+    if names.iter().any(|n| n.len() > MAX_DASHED_DIR_LEN) {
+        return None;
+    }
+    Some(names)
+}
+
+/// Project dirs under `base` for the repo, as named by any of `repo_paths`, and any of its
+/// subdirectories (for monorepos, where sessions are started in a package dir). Returns None
+/// when the encoding can't be predicted and the caller must walk everything. Candidates may
+/// include sibling repos that share a prefix (`repo-2`); session cwds are still checked, so
+/// that only costs a few reads.
+fn dashed_project_dirs(base: &Path, repo_paths: &[PathBuf]) -> Option<Vec<PathBuf>> {
+    let names = repo_paths
+        .iter()
+        .map(|path| dashed_dir_names(path))
+        .collect::<Option<Vec<_>>>()?
+        .concat();
+    let entries = fs::read_dir(base).ok()?;
+    Some(
+        entries
+            .flatten()
+            .filter(|entry| {
+                let file_name = entry.file_name();
+                let Some(dir_name) = file_name.to_str() else {
+                    return false;
+                };
+                names.iter().any(|name| {
+                    dir_name == name || (dir_name.starts_with(name.as_str()) && dir_name[name.len()..].starts_with('-'))
+                })
+            })
+            .map(|entry| entry.path())
+            .collect(),
+    )
+}
+
+fn sha256_hex(path: &Path) -> String {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(path.as_os_str().as_encoded_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// The repo root, its subdirectories down to MAX_HASHED_DIR_DEPTH (skipping hidden and
+/// build output dirs), and the directories between the root and the current directory, since
+/// an agent may have been started in any of them.
+fn project_root_candidates(repo_path: &Path) -> Vec<PathBuf> {
+    let mut candidates = vec![repo_path.to_path_buf()];
+    let mut level = vec![repo_path.to_path_buf()];
+    for _ in 0..MAX_HASHED_DIR_DEPTH {
+        let mut next = Vec::new();
+        for dir in &level {
+            let Ok(entries) = fs::read_dir(dir) else { continue };
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                if name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref()) {
+                    continue;
+                }
+                if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    next.push(entry.path());
+                }
+            }
+        }
+        candidates.extend(next.iter().cloned());
+        if candidates.len() >= MAX_HASHED_DIRS {
+            candidates.truncate(MAX_HASHED_DIRS);
+            break;
+        }
+        level = next;
+    }
+    if let Ok(current_dir) = std::env::current_dir() {
+        for dir in current_dir.ancestors().take_while(|d| d.starts_with(repo_path)) {
+            if !candidates.iter().any(|c| c == dir) {
+                candidates.push(dir.to_path_buf());
+            }
+        }
+    }
+    candidates
+}
+
+/// Whether anything under `dir` (down to `depth` levels) was modified since the cutoff.
+fn has_recent_file(dir: &Path, cutoff: SystemTime, depth: usize) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
     };
-    let breadcrumb_ext = agent.breadcrumb_ext.unwrap_or("jsonl");
+    entries.flatten().any(|entry| {
+        let path = entry.path();
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            depth > 0 && has_recent_file(&path, cutoff, depth - 1)
+        } else {
+            is_recent(&path, cutoff)
+        }
+    })
+}
+
+/// Look for per-project dirs named by the hash of the repo root or one of its subdirectories,
+/// as named by any of the repo's aliases.
+fn find_hashed_project_dir(scan: &Scan, base: &Path) -> bool {
+    let Ok(entries) = fs::read_dir(base) else {
+        return false;
+    };
+    let existing: std::collections::HashSet<_> = entries.flatten().map(|e| e.file_name()).collect();
+    let aliases = scan.matcher.aliases();
+    let candidates: Vec<PathBuf> = project_root_candidates(scan.repo_path)
+        .iter()
+        .filter_map(|candidate| candidate.strip_prefix(scan.repo_path).ok())
+        .flat_map(|rest| aliases.iter().map(move |alias| paths::join(alias, rest)))
+        .collect();
+
+    candidates.iter().any(|candidate| {
+        let hash = sha256_hex(candidate);
+        if !existing.contains(std::ffi::OsStr::new(&hash)) {
+            return false;
+        }
+        let dir = base.join(&hash);
+        if scan.owner.is_some() && user::owner_of(&dir) != scan.owner {
+            return false;
+        }
+        let recent = has_recent_file(&dir, scan.cutoff, 2);
+        if scan.debug {
+            eprintln!("    {} ({}) recent: {}", dir.display(), candidate.display(), recent);
+        }
+        recent && scan.found(&dir)
+    })
+}
+
+/// Query a session database for working directories active since the cutoff. The database
+/// is opened read-only and skipped entirely unless it (or its WAL) was modified recently.
+fn sqlite_has_matching_cwd(scan: &Scan, db: &Path, query: &str) -> bool {
+    let wal = db.with_file_name(format!(
+        "{}-wal",
+        db.file_name().and_then(|n| n.to_str()).unwrap_or_default()
+    ));
+    if !is_recent(db, scan.cutoff) && !is_recent(&wal, scan.cutoff) {
+        return false;
+    }
+    if scan.owner.is_some() && user::owner_of(db) != scan.owner {
+        if scan.debug {
+            eprintln!("    {} belongs to another user, skipping", db.display());
+        }
+        return false;
+    }
+
+    let cutoff_secs = scan
+        .cutoff
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    let result = (|| -> rusqlite::Result<Vec<String>> {
+        let conn = rusqlite::Connection::open_with_flags(db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        conn.busy_timeout(std::time::Duration::from_millis(100))?;
+        let mut stmt = conn.prepare(query)?;
+        let rows = stmt.query_map([cutoff_secs], |row| row.get::<_, String>(0))?;
+        rows.collect()
+    })();
+
+    match result {
+        Ok(cwds) => cwds.iter().any(|cwd| {
+            if scan.debug {
+                eprintln!("    {} cwd: {}", db.display(), cwd);
+            }
+            cwd_matches_repo(cwd, &scan.matcher) && scan.found(db)
+        }),
+        Err(e) => {
+            if scan.debug {
+                eprintln!("    Failed to query {}: {}", db.display(), e);
+            }
+            false
+        }
+    }
+}
+
+/// How sessions are laid out in an agent's breadcrumb dir: the lookups that go through it.
+#[derive(Clone, Copy)]
+enum SessionLayout {
+    Walk,
+    DashedProjectDir,
+    HashedProjectDir,
+}
 
-    let home = match home_dir() {
-        Some(h) => h,
+fn check_breadcrumb_dir(agent: &'static Agent, scan: &mut Scan, layout: SessionLayout) -> bool {
+    let debug = scan.debug;
+    let base = match breadcrumb_dir(agent) {
+        Some(d) => d,
         None => return false,
     };
-    let base = Path::new(&home).join(breadcrumb_dir);
+    let breadcrumb_ext = agent.breadcrumb_ext.unwrap_or("jsonl");
 
     if debug {
         eprintln!("  {} breadcrumb dir: {}", agent.email, base.display());
@@ -114,38 +396,370 @@ fn check_source(agent: &'static Agent, repo_path: &Path, cutoff: SystemTime, deb
         return false;
     }
 
-    let matched = find_session_file_with_cwd(&base, breadcrumb_ext, repo_path, cutoff, debug);
+    let found_session_file = match layout {
+        SessionLayout::DashedProjectDir => match dashed_project_dirs(&base, &scan.matcher.aliases()) {
+            Some(dirs) => {
+                if debug {
+                    eprintln!("    Checking {} project dir(s) for this repo", dirs.len());
+                }
+                dirs.iter()
+                    .any(|dir| find_session_file_with_cwd(scan, agent, dir, breadcrumb_ext))
+            }
+            None => {
+                if debug {
+                    eprintln!("    Project dir name is ambiguous, walking all projects");
+                }
+                find_session_file_with_cwd(scan, agent, &base, breadcrumb_ext)
+            }
+        },
+        SessionLayout::HashedProjectDir => find_hashed_project_dir(scan, &base),
+        SessionLayout::Walk => find_session_file_with_cwd(scan, agent, &base, breadcrumb_ext),
+    };
+
+    found_session_file
+        || agent
+            .breadcrumb_sqlite
+            .as_ref()
+            .is_some_and(|source| sqlite_has_matching_cwd(scan, &base.join(source.file), source.query))
+}
 
-    if !matched && debug {
+/// Parse a local "YYYY-MM-DD HH:MM:SS" timestamp (fractional seconds ignored), as written by
+/// aider's history files.
+fn parse_local_timestamp(s: &str) -> Option<SystemTime> {
+    let (date, time) = s.trim().split_once(' ')?;
+    let mut date = date.splitn(3, '-').map(|p| p.parse::<i32>());
+    let time = time.split('.').next()?;
+    let mut time = time.splitn(3, ':').map(|p| p.parse::<i32>());
+
+    // SAFETY: an all-zero tm is a valid value, and mktime only reads and normalises it.
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    tm.tm_year = date.next()?.ok()? - 1900;
+    tm.tm_mon = date.next()?.ok()? - 1;
+    tm.tm_mday = date.next()?.ok()?;
+    tm.tm_hour = time.next()?.ok()?;
+    tm.tm_min = time.next()?.ok()?;
+    tm.tm_sec = time.next()?.ok()?;
+    tm.tm_isdst = -1;
+    let secs = unsafe { libc::mktime(&mut tm) };
+    u64::try_from(secs)
+        .ok()
+        .map(|secs| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+}
+
+/// The last session header in an aider history file: `# aider chat started at <ts>` in the
+/// chat history, or the `# <ts>` line before each prompt in the input history. Only the tail
+/// of the file is read.
+fn last_history_timestamp(path: &Path) -> Option<SystemTime> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = fs::File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(HISTORY_TAIL_BYTES)))
+        .ok()?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).ok()?;
+
+    String::from_utf8_lossy(&tail).lines().rev().find_map(|line| {
+        let header = line.strip_prefix("# ")?;
+        parse_local_timestamp(header.strip_prefix("aider chat started at ").unwrap_or(header))
+    })
+}
+
+/// Most recent mtime of a path, looking one level into directories (aider's tags cache is
+/// a directory whose contents change, not the directory itself).
+fn latest_mtime(path: &Path) -> Option<SystemTime> {
+    let own = path.metadata().and_then(|m| m.modified()).ok();
+    if !path.is_dir() {
+        return own;
+    }
+    fs::read_dir(path)
+        .ok()?
+        .flatten()
+        .filter_map(|e| e.metadata().and_then(|m| m.modified()).ok())
+        .chain(own)
+        .max()
+}
+
+/// Check files an agent keeps in the repository root. A file counts when it was modified
+/// since the cutoff; history files must also have a session header from the last day, so a
+/// checkout or backup restore that bumps their mtime isn't mistaken for a session.
+fn check_repo_files(scan: &Scan, patterns: &[&str]) -> bool {
+    let Ok(entries) = fs::read_dir(scan.repo_path) else {
+        return false;
+    };
+    let max_session_age = SystemTime::now() - std::time::Duration::from_secs(MAX_SESSION_AGE_SECS);
+
+    entries.flatten().any(|entry| {
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            return false;
+        };
+        let matches_pattern = patterns.iter().any(|p| match p.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == *p,
+        });
+        if !matches_pattern {
+            return false;
+        }
+
+        let path = entry.path();
+        let recent = latest_mtime(&path).is_some_and(|mtime| mtime >= scan.cutoff);
+        if scan.debug {
+            eprintln!("    {} recent: {}", path.display(), recent);
+        }
+        if !recent || path.is_dir() {
+            return recent && scan.found(&path);
+        }
+        match last_history_timestamp(&path) {
+            Some(started) => {
+                if scan.debug {
+                    eprintln!("    {} last session header: {:?}", path.display(), started);
+                }
+                started >= max_session_age && scan.found(&path)
+            }
+            None => scan.found(&path),
+        }
+    })
+}
+
+fn check_source(agent: &'static Agent, scan: &mut Scan) -> bool {
+    let matched = match agent.breadcrumb_lookup {
+        BreadcrumbLookup::InRepo(patterns) => {
+            if scan.debug {
+                eprintln!("  {} in-repo breadcrumbs: {}", agent.email, scan.repo_path.display());
+            }
+            check_repo_files(scan, patterns)
+        }
+        BreadcrumbLookup::VsCodeWorkspace { apps, activity } => {
+            if scan.debug {
+                eprintln!("  {} editor workspaces: {}", agent.email, apps.join(", "));
+            }
+            vscode::check_workspaces(scan, apps, &activity)
+        }
+        BreadcrumbLookup::VsCodeTaskHistory { apps, extension } => {
+            if scan.debug {
+                eprintln!("  {} task history: {}", agent.email, extension);
+            }
+            vscode::check_task_history(scan, apps, extension)
+        }
+        BreadcrumbLookup::JetBrains { plugin_dirs } => {
+            if scan.debug {
+                eprintln!("  {} JetBrains plugin state: {}", agent.email, plugin_dirs.join(", "));
+            }
+            jetbrains::check_ide_state(scan, plugin_dirs)
+        }
+        BreadcrumbLookup::Nvim(history) => {
+            if scan.debug {
+                eprintln!("  {} Neovim history", agent.email);
+            }
+            nvim::check_history(scan, agent, history)
+        }
+        BreadcrumbLookup::Walk => check_breadcrumb_dir(agent, scan, SessionLayout::Walk),
+        BreadcrumbLookup::DashedProjectDir => check_breadcrumb_dir(agent, scan, SessionLayout::DashedProjectDir),
+        BreadcrumbLookup::HashedProjectDir => check_breadcrumb_dir(agent, scan, SessionLayout::HashedProjectDir),
+    };
+
+    if !matched && scan.debug {
         eprintln!("    No match for {}", agent.email);
     }
 
     matched
 }
 
-pub fn detect_agents_from_breadcrumbs(repo_path: &Path, debug: bool) -> Vec<&'static Agent> {
-    let cutoff = SystemTime::now() - std::time::Duration::from_secs(CUTOFF_SECS);
+/// Agents with breadcrumbs for the repo, each with the file or dir that matched.
+pub fn detect_breadcrumbs(repo_path: &Path, config: &Config, debug: bool) -> Vec<(&'static Agent, Option<PathBuf>)> {
+    let mut scan = Scan::new(repo_path, config, debug);
     let mut agents = Vec::new();
 
     if debug {
         eprintln!("\n=== Breadcrumb Fallback ===");
     }
 
-    for agent in KNOWN_AGENTS {
-        if check_source(agent, repo_path, cutoff, debug) {
-            agents.push(agent);
+    for agent in Agent::all() {
+        scan.evidence.take();
+        if check_source(agent, &mut scan) {
+            agents.push((agent, scan.evidence.take()));
         }
     }
 
+    if let Err(e) = scan.cache.save()
+        && debug
+    {
+        eprintln!("  Failed to save session cache: {}", e);
+    }
+
     agents
 }
 
+pub fn detect_agents_from_breadcrumbs(repo_path: &Path, config: &Config, debug: bool) -> Vec<&'static Agent> {
+    detect_breadcrumbs(repo_path, config, debug)
+        .into_iter()
+        .map(|(agent, _)| agent)
+        .collect()
+}
+
+/// Check a single agent's breadcrumbs for the repo, tracing each step to stderr.
+pub fn explain_agent(agent: &'static Agent, repo_path: &Path, config: &Config) -> bool {
+    let mut scan = Scan::new(repo_path, config, true);
+    let matched = check_source(agent, &mut scan);
+    let _ = scan.cache.save();
+    matched
+}
+
+/// A place an agent's breadcrumbs are looked for, as listed by `aittributor agents`.
+pub struct Source {
+    pub path: PathBuf,
+    /// Sessions (files, project dirs or database rows) active since the cutoff, where they
+    /// can be counted cheaply.
+    pub recent: Option<usize>,
+}
+
+/// Files with `ext` modified since the cutoff, down to `depth` levels below `dir`.
+fn count_recent_files(dir: &Path, cutoff: SystemTime, depth: usize, ext: &str) -> usize {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| {
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                if depth > 0 {
+                    count_recent_files(&path, cutoff, depth - 1, ext)
+                } else {
+                    0
+                }
+            } else {
+                usize::from(has_extension(&path, ext) && is_recent(&path, cutoff))
+            }
+        })
+        .sum()
+}
+
+/// Subdirectories of `dir` with anything modified since the cutoff.
+fn count_recent_dirs(dir: &Path, cutoff: SystemTime, depth: usize) -> usize {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()) && has_recent_file(&entry.path(), cutoff, depth))
+        .count()
+}
+
+fn count_sqlite_rows(db: &Path, query: &str, cutoff: SystemTime) -> Option<usize> {
+    let cutoff_secs = cutoff
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    let conn = rusqlite::Connection::open_with_flags(db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY).ok()?;
+    conn.busy_timeout(std::time::Duration::from_millis(100)).ok()?;
+    let count = conn
+        .query_row(&format!("SELECT COUNT(*) FROM ({})", query), [cutoff_secs], |row| {
+            row.get::<_, i64>(0)
+        })
+        .ok()?;
+    usize::try_from(count).ok()
+}
+
+/// Where an agent's breadcrumbs are looked for on this machine, with recent session counts.
+/// In-repo breadcrumbs have no machine-wide location and yield nothing.
+pub fn sources(agent: &Agent) -> Vec<Source> {
+    /// Deep enough for date-partitioned session dirs like Codex's `YYYY/MM/DD/`.
+    const MAX_COUNT_DEPTH: usize = 4;
+
+    let cutoff = SystemTime::now() - std::time::Duration::from_secs(CUTOFF_SECS);
+    let mut sources = Vec::new();
+    match agent.breadcrumb_lookup {
+        BreadcrumbLookup::InRepo(_) => {}
+        BreadcrumbLookup::VsCodeWorkspace { apps, .. } => {
+            for user in apps.iter().filter_map(|app| vscode::user_dir(app)) {
+                let path = user.join("workspaceStorage");
+                let recent = Some(count_recent_dirs(&path, cutoff, 1));
+                sources.push(Source { path, recent });
+            }
+        }
+        BreadcrumbLookup::VsCodeTaskHistory { apps, extension } => {
+            for user in apps.iter().filter_map(|app| vscode::user_dir(app)) {
+                let path = user.join("globalStorage").join(extension);
+                sources.push(Source { path, recent: None });
+            }
+        }
+        BreadcrumbLookup::JetBrains { .. } => {
+            for path in jetbrains::jetbrains_roots() {
+                sources.push(Source { path, recent: None });
+            }
+        }
+        BreadcrumbLookup::Nvim(history) => {
+            if let Some(path) = nvim::history_dir(history) {
+                let recent = match history {
+                    NvimHistory::Avante => count_recent_dirs(&path, cutoff, 1),
+                    NvimHistory::CodeCompanion => count_recent_files(&path, cutoff, 0, "json"),
+                };
+                sources.push(Source {
+                    path,
+                    recent: Some(recent),
+                });
+            }
+        }
+        BreadcrumbLookup::Walk | BreadcrumbLookup::DashedProjectDir | BreadcrumbLookup::HashedProjectDir => {
+            let Some(base) = breadcrumb_dir(agent) else {
+                return sources;
+            };
+            let recent = if agent.breadcrumb_lookup == BreadcrumbLookup::HashedProjectDir {
+                count_recent_dirs(&base, cutoff, MAX_HASHED_DIR_DEPTH)
+            } else {
+                count_recent_files(&base, cutoff, MAX_COUNT_DEPTH, agent.breadcrumb_ext.unwrap_or("jsonl"))
+            };
+            if let Some(source) = &agent.breadcrumb_sqlite {
+                let path = base.join(source.file);
+                let recent = count_sqlite_rows(&path, source.query, cutoff);
+                sources.push(Source { path, recent });
+            }
+            sources.insert(
+                0,
+                Source {
+                    path: base,
+                    recent: Some(recent),
+                },
+            );
+        }
+    }
+    sources
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
     use std::io::Write;
 
+    /// A scan of `repo_path` for activity since `cutoff`, with no session cache or owner.
+    pub(super) fn scan(repo_path: &Path, cutoff: SystemTime) -> Scan<'_> {
+        Scan {
+            repo_path,
+            matcher: PathMatcher::new(&[repo_path.to_path_buf()], &[]),
+            cutoff,
+            owner: None,
+            cache: SessionCache::default(),
+            debug: false,
+            evidence: Default::default(),
+        }
+    }
+
+    /// A scan for activity in the last hour.
+    pub(super) fn recent_scan(repo_path: &Path) -> Scan<'_> {
+        scan(repo_path, SystemTime::now() - std::time::Duration::from_secs(60 * 60))
+    }
+
+    fn codex() -> &'static Agent {
+        Agent::find_by_name("codex").unwrap()
+    }
+
+    fn file_has_matching_cwd(path: &Path, repo_path: &Path, debug: bool) -> bool {
+        read_session_cwd(path, debug)
+            .is_some_and(|cwd| cwd_matches_repo(&cwd, &PathMatcher::new(&[repo_path.to_path_buf()], &[])))
+    }
+
     #[test]
     fn test_extract_cwd_from_json() {
         let line = r#"{"type":"session_meta","cwd":"/Users/foo/myrepo","branch":"main"}"#;
@@ -162,18 +776,18 @@ mod tests {
     fn test_cwd_matches_repo_uses_path_components() {
         assert!(cwd_matches_repo(
             "/Users/foo/monorepo/apps/service-a",
-            Path::new("/Users/foo/monorepo")
+            &PathMatcher::new(&[PathBuf::from("/Users/foo/monorepo")], &[])
         ));
         assert!(!cwd_matches_repo(
             "/Users/foo/aittributor2",
-            Path::new("/Users/foo/aittributor")
+            &PathMatcher::new(&[PathBuf::from("/Users/foo/aittributor")], &[])
         ));
     }
 
     #[test]
     fn test_no_breadcrumbs_returns_empty() {
         let dir = tempfile::TempDir::new().unwrap();
-        let agents = detect_agents_from_breadcrumbs(dir.path(), false);
+        let agents = detect_agents_from_breadcrumbs(dir.path(), &Config::default(), false);
         assert!(agents.is_empty());
     }
 
@@ -225,21 +839,16 @@ mod tests {
         writeln!(f, r#"{{"type":"session_meta","cwd":"/Users/foo/myrepo"}}"#).unwrap();
 
         // Matching repo
-        assert!(find_session_file_with_cwd(
-            dir.path(),
-            "jsonl",
-            Path::new("/Users/foo/myrepo"),
-            cutoff,
-            false
-        ));
+        let mut matching = scan(Path::new("/Users/foo/myrepo"), cutoff);
+        assert!(find_session_file_with_cwd(&mut matching, codex(), dir.path(), "jsonl"));
+        assert_eq!(matching.evidence.take(), Some(day_dir.join("session.jsonl")));
 
         // Non-matching repo
         assert!(!find_session_file_with_cwd(
+            &mut scan(Path::new("/Users/bar/other"), cutoff),
+            codex(),
             dir.path(),
-            "jsonl",
-            Path::new("/Users/bar/other"),
-            cutoff,
-            false
+            "jsonl"
         ));
     }
 
@@ -254,11 +863,10 @@ mod tests {
         writeln!(f, r#"{{"type":"session_meta","cwd":"/Users/foo/aittributor2"}}"#).unwrap();
 
         assert!(!find_session_file_with_cwd(
+            &mut scan(Path::new("/Users/foo/aittributor"), cutoff),
+            codex(),
             dir.path(),
-            "jsonl",
-            Path::new("/Users/foo/aittributor"),
-            cutoff,
-            false
+            "jsonl"
         ));
     }
 
@@ -278,11 +886,459 @@ mod tests {
 
         // Commit can run from another folder in the same repo; we match by git root.
         assert!(find_session_file_with_cwd(
+            &mut scan(Path::new("/Users/foo/monorepo"), cutoff),
+            codex(),
             dir.path(),
-            "jsonl",
-            Path::new("/Users/foo/monorepo"),
-            cutoff,
-            false
+            "jsonl"
         ));
     }
+
+    #[test]
+    fn test_find_session_file_with_cwd_ignores_other_users_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cutoff = SystemTime::now() - std::time::Duration::from_secs(10);
+
+        let mut f = fs::File::create(dir.path().join("session.jsonl")).unwrap();
+        writeln!(f, r#"{{"type":"session_meta","cwd":"/Users/foo/myrepo"}}"#).unwrap();
+
+        let repo = Path::new("/Users/foo/myrepo");
+        let uid = user::current_uid();
+        let mut own = Scan {
+            owner: Some(uid),
+            ..scan(repo, cutoff)
+        };
+        assert!(find_session_file_with_cwd(&mut own, codex(), dir.path(), "jsonl"));
+        let mut other = Scan {
+            owner: Some(uid + 1),
+            ..scan(repo, cutoff)
+        };
+        assert!(!find_session_file_with_cwd(&mut other, codex(), dir.path(), "jsonl"));
+    }
+
+    fn env_from(vars: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |key| vars.iter().find(|(k, _)| *k == key).map(|(_, v)| v.to_string())
+    }
+
+    #[test]
+    fn test_resolve_breadcrumb_dir_defaults_to_home() {
+        let claude = Agent::find_by_name("claude").unwrap();
+        assert_eq!(
+            resolve_breadcrumb_dir(claude, Some(Path::new("/home/me")), env_from(&[])),
+            Some(PathBuf::from("/home/me/.claude/projects"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_breadcrumb_dir_prefers_override_var() {
+        let claude = Agent::find_by_name("claude").unwrap();
+        assert_eq!(
+            resolve_breadcrumb_dir(
+                claude,
+                Some(Path::new("/home/me")),
+                env_from(&[("CLAUDE_CONFIG_DIR", "/data/claude")])
+            ),
+            Some(PathBuf::from("/data/claude/projects"))
+        );
+
+        let codex = Agent::find_by_name("codex").unwrap();
+        assert_eq!(
+            resolve_breadcrumb_dir(codex, None, env_from(&[("CODEX_HOME", "/srv/codex")])),
+            Some(PathBuf::from("/srv/codex/sessions"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_breadcrumb_dir_ignores_empty_override() {
+        let codex = Agent::find_by_name("codex").unwrap();
+        assert_eq!(
+            resolve_breadcrumb_dir(codex, Some(Path::new("/home/me")), env_from(&[("CODEX_HOME", "")])),
+            Some(PathBuf::from("/home/me/.codex/sessions"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_breadcrumb_dir_uses_xdg_when_set() {
+        let copilot = Agent::find_by_name("copilot").unwrap();
+        assert_eq!(
+            resolve_breadcrumb_dir(
+                copilot,
+                Some(Path::new("/home/me")),
+                env_from(&[("XDG_CONFIG_HOME", "/home/me/.cfg")])
+            ),
+            Some(PathBuf::from("/home/me/.cfg/.copilot/session-state"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_breadcrumb_dir_none_without_breadcrumbs() {
+        let amp = Agent::find_by_name("amp").unwrap();
+        assert_eq!(
+            resolve_breadcrumb_dir(amp, Some(Path::new("/home/me")), env_from(&[])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_dashed_dir_names() {
+        let names = dashed_dir_names(Path::new("/Users/foo/my.repo_x")).unwrap();
+        assert!(names.contains(&"-Users-foo-my.repo_x".to_string()));
+        assert!(names.contains(&"-Users-foo-my-repo_x".to_string()));
+        assert!(names.contains(&"-Users-foo-my-repo-x".to_string()));
+    }
+
+    #[test]
+    fn test_dashed_dir_names_ambiguous() {
+        assert_eq!(dashed_dir_names(Path::new("/Users/foo/réseau")), None);
+        let long = format!("/Users/foo/{}", "a".repeat(MAX_DASHED_DIR_LEN));
+        assert_eq!(dashed_dir_names(Path::new(&long)), None);
+    }
+
+    #[test]
+    fn test_dashed_project_dirs_selects_repo_and_subdirs() {
+        let base = tempfile::TempDir::new().unwrap();
+        for name in [
+            "-Users-foo-monorepo",
+            "-Users-foo-monorepo-apps-api",
+            "-Users-foo-monorepository",
+            "-Users-foo-other",
+        ] {
+            fs::create_dir(base.path().join(name)).unwrap();
+        }
+
+        let mut dirs = dashed_project_dirs(base.path(), &[PathBuf::from("/Users/foo/monorepo")]).unwrap();
+        dirs.sort();
+        assert_eq!(
+            dirs,
+            vec![
+                base.path().join("-Users-foo-monorepo"),
+                base.path().join("-Users-foo-monorepo-apps-api"),
+            ]
+        );
+    }
+
+    fn local_timestamp(time: SystemTime) -> String {
+        let secs = time.duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs() as libc::time_t;
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        unsafe { libc::localtime_r(&secs, &mut tm) };
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            tm.tm_year + 1900,
+            tm.tm_mon + 1,
+            tm.tm_mday,
+            tm.tm_hour,
+            tm.tm_min,
+            tm.tm_sec
+        )
+    }
+
+    fn aider() -> &'static [&'static str] {
+        match Agent::find_by_name("aider").unwrap().breadcrumb_lookup {
+            BreadcrumbLookup::InRepo(patterns) => patterns,
+            _ => panic!("aider should use in-repo breadcrumbs"),
+        }
+    }
+
+    #[test]
+    fn test_parse_local_timestamp_round_trips() {
+        let now = SystemTime::now();
+        let parsed = parse_local_timestamp(&local_timestamp(now)).unwrap();
+        assert!(now.duration_since(parsed).unwrap() < std::time::Duration::from_secs(1));
+        assert!(parse_local_timestamp("2025-06-15 10:23:45.123456").is_some());
+        assert_eq!(parse_local_timestamp("not a date"), None);
+    }
+
+    #[test]
+    fn test_last_history_timestamp_finds_last_header() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(".aider.chat.history.md");
+        fs::write(
+            &path,
+            "\n# aider chat started at 2024-01-01 09:00:00\n\n> hi\n\n# aider chat started at 2024-01-02 09:00:00\n\n#### do it\n",
+        )
+        .unwrap();
+        assert_eq!(
+            last_history_timestamp(&path),
+            parse_local_timestamp("2024-01-02 09:00:00")
+        );
+    }
+
+    #[test]
+    fn test_check_repo_files_detects_recent_aider_session() {
+        let repo = tempfile::TempDir::new().unwrap();
+        let cutoff = SystemTime::now() - std::time::Duration::from_secs(10);
+        fs::write(
+            repo.path().join(".aider.chat.history.md"),
+            format!(
+                "\n# aider chat started at {}\n\n#### add tests\n",
+                local_timestamp(SystemTime::now())
+            ),
+        )
+        .unwrap();
+
+        assert!(check_repo_files(&scan(repo.path(), cutoff), aider()));
+    }
+
+    #[test]
+    fn test_check_repo_files_rejects_stale_session_header() {
+        let repo = tempfile::TempDir::new().unwrap();
+        let cutoff = SystemTime::now() - std::time::Duration::from_secs(10);
+        // Recently modified (e.g. restored from backup), but the last session was long ago.
+        fs::write(
+            repo.path().join(".aider.chat.history.md"),
+            "\n# aider chat started at 2020-01-01 09:00:00\n\n#### old\n",
+        )
+        .unwrap();
+
+        assert!(!check_repo_files(&scan(repo.path(), cutoff), aider()));
+    }
+
+    #[test]
+    fn test_check_repo_files_matches_tags_cache_prefix() {
+        let repo = tempfile::TempDir::new().unwrap();
+        let cutoff = SystemTime::now() - std::time::Duration::from_secs(10);
+        let cache = repo.path().join(".aider.tags.cache.v4");
+        fs::create_dir(&cache).unwrap();
+        fs::write(cache.join("cache.db"), "").unwrap();
+        fs::write(repo.path().join("README.md"), "").unwrap();
+
+        assert!(check_repo_files(&scan(repo.path(), cutoff), aider()));
+        assert!(!check_repo_files(&scan(repo.path(), cutoff), &[".aider.input.history"]));
+    }
+
+    fn goose() -> &'static Agent {
+        Agent::find_by_name("goose").unwrap()
+    }
+
+    #[test]
+    fn test_extract_cwd_from_goose_metadata() {
+        let line = r#"{"working_dir":"/Users/foo/myrepo","description":"fix tests","message_count":4}"#;
+        assert_eq!(extract_cwd_from_json(line), Some("/Users/foo/myrepo"));
+    }
+
+    #[test]
+    fn test_goose_jsonl_session() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cutoff = SystemTime::now() - std::time::Duration::from_secs(10);
+        let mut f = fs::File::create(dir.path().join("20250615_101500.jsonl")).unwrap();
+        writeln!(
+            f,
+            r#"{{"working_dir":"/Users/foo/myrepo","description":"","schedule_id":null,"message_count":2}}"#
+        )
+        .unwrap();
+        writeln!(f, r#"{{"role":"user","created":1750000000,"content":[]}}"#).unwrap();
+
+        assert!(find_session_file_with_cwd(
+            &mut scan(Path::new("/Users/foo/myrepo"), cutoff),
+            goose(),
+            dir.path(),
+            "jsonl"
+        ));
+        assert!(!find_session_file_with_cwd(
+            &mut scan(Path::new("/Users/foo/other"), cutoff),
+            goose(),
+            dir.path(),
+            "jsonl"
+        ));
+    }
+
+    #[test]
+    fn test_warp_ai_queries() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cutoff = SystemTime::now() - std::time::Duration::from_secs(60 * 60);
+        let db = dir.path().join("warp.sqlite");
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE ai_queries (
+                id INTEGER PRIMARY KEY,
+                exchange_id TEXT NOT NULL,
+                conversation_id TEXT NOT NULL,
+                start_ts DATETIME NOT NULL,
+                input TEXT NOT NULL,
+                working_directory TEXT
+            );
+            INSERT INTO ai_queries (exchange_id, conversation_id, start_ts, input, working_directory)
+                VALUES ('a', 'c1', CURRENT_TIMESTAMP, 'fix the build', '/Users/foo/myrepo'),
+                       ('b', 'c2', datetime('now', '-3 hours'), 'old', '/Users/foo/stale');",
+        )
+        .unwrap();
+        let warp = crate::agent::KNOWN_AGENTS
+            .iter()
+            .find(|a| a.email.starts_with("Warp"))
+            .unwrap();
+        let query = warp.breadcrumb_sqlite.as_ref().unwrap().query;
+
+        assert!(sqlite_has_matching_cwd(
+            &scan(Path::new("/Users/foo/myrepo"), cutoff),
+            &db,
+            query
+        ));
+        assert!(!sqlite_has_matching_cwd(
+            &scan(Path::new("/Users/foo/stale"), cutoff),
+            &db,
+            query
+        ));
+    }
+
+    #[test]
+    fn test_zed_agent_threads() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cutoff = SystemTime::now() - std::time::Duration::from_secs(60 * 60);
+        let db = dir.path().join("threads.db");
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE threads (
+                id TEXT PRIMARY KEY,
+                summary TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                data_type TEXT NOT NULL,
+                data BLOB NOT NULL,
+                folder_paths TEXT
+            );
+            INSERT INTO threads VALUES
+                ('a', 'Fix the build', strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), 'zstd', x'', '/Users/foo/myrepo' || char(10) || '/Users/foo/docs'),
+                ('b', 'Old', strftime('%Y-%m-%dT%H:%M:%SZ', 'now', '-3 hours'), 'zstd', x'', '/Users/foo/stale'),
+                ('c', 'No project', strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), 'zstd', x'', NULL);",
+        )
+        .unwrap();
+        let zed = crate::agent::KNOWN_AGENTS
+            .iter()
+            .find(|a| a.email.starts_with("Zed"))
+            .unwrap();
+        let query = zed.breadcrumb_sqlite.as_ref().unwrap().query;
+
+        assert!(sqlite_has_matching_cwd(
+            &scan(Path::new("/Users/foo/myrepo"), cutoff),
+            &db,
+            query
+        ));
+        assert!(!sqlite_has_matching_cwd(
+            &scan(Path::new("/Users/foo/stale"), cutoff),
+            &db,
+            query
+        ));
+    }
+
+    fn goose_sessions_db(path: &Path, rows: &[(&str, &str)]) {
+        let conn = rusqlite::Connection::open(path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE sessions (
+                id TEXT PRIMARY KEY,
+                description TEXT NOT NULL DEFAULT '',
+                working_dir TEXT NOT NULL,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )",
+        )
+        .unwrap();
+        for (i, (working_dir, updated_at)) in rows.iter().enumerate() {
+            conn.execute(
+                &format!(
+                    "INSERT INTO sessions (id, working_dir, updated_at) VALUES (?1, ?2, {})",
+                    updated_at
+                ),
+                (i.to_string(), working_dir),
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_goose_sqlite_session() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cutoff = SystemTime::now() - std::time::Duration::from_secs(60 * 60);
+        let db = dir.path().join("sessions.db");
+        goose_sessions_db(
+            &db,
+            &[
+                ("/Users/foo/myrepo/app", "CURRENT_TIMESTAMP"),
+                ("/Users/foo/stale", "datetime('now', '-3 hours')"),
+            ],
+        );
+        let query = goose().breadcrumb_sqlite.as_ref().unwrap().query;
+
+        assert!(sqlite_has_matching_cwd(
+            &scan(Path::new("/Users/foo/myrepo"), cutoff),
+            &db,
+            query
+        ));
+        // Only sessions updated since the cutoff count.
+        assert!(!sqlite_has_matching_cwd(
+            &scan(Path::new("/Users/foo/stale"), cutoff),
+            &db,
+            query
+        ));
+        assert!(!sqlite_has_matching_cwd(
+            &scan(Path::new("/Users/foo/other"), cutoff),
+            &db,
+            query
+        ));
+    }
+
+    #[test]
+    fn test_sqlite_missing_db() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cutoff = SystemTime::now() - std::time::Duration::from_secs(60);
+        let query = goose().breadcrumb_sqlite.as_ref().unwrap().query;
+        assert!(!sqlite_has_matching_cwd(
+            &scan(Path::new("/Users/foo/myrepo"), cutoff),
+            &dir.path().join("sessions.db"),
+            query
+        ));
+    }
+
+    #[test]
+    fn test_sha256_hex_matches_gemini_project_hash() {
+        // echo -n /Users/foo/myrepo | shasum -a 256
+        assert_eq!(
+            sha256_hex(Path::new("/Users/foo/myrepo")),
+            "3d8b5dc63ccfe168f83eeb04de80f172b0e176da0229f137390a56cf17130fe6"
+        );
+    }
+
+    #[test]
+    fn test_find_hashed_project_dir_for_repo_and_subdir() {
+        let base = tempfile::TempDir::new().unwrap();
+        let repo = tempfile::TempDir::new().unwrap();
+        let cutoff = SystemTime::now() - std::time::Duration::from_secs(10);
+        let package = repo.path().join("packages").join("api");
+        fs::create_dir_all(&package).unwrap();
+
+        assert!(!find_hashed_project_dir(&scan(repo.path(), cutoff), base.path()));
+
+        let project_dir = base.path().join(sha256_hex(&package));
+        fs::create_dir_all(project_dir.join("chats")).unwrap();
+        fs::write(project_dir.join("chats").join("session-1.json"), "{}").unwrap();
+
+        assert!(find_hashed_project_dir(&scan(repo.path(), cutoff), base.path()));
+    }
+
+    #[test]
+    fn test_find_hashed_project_dir_through_symlinked_root() {
+        let base = tempfile::TempDir::new().unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let real = dir.path().join("data").join("repo");
+        fs::create_dir_all(real.join("src")).unwrap();
+        let link = dir.path().join("src");
+        std::os::unix::fs::symlink(dir.path().join("data"), &link).unwrap();
+        let cutoff = SystemTime::now() - std::time::Duration::from_secs(10);
+
+        // The agent hashed the resolved path of a subdirectory; the repo is reached via the link.
+        let project_dir = base.path().join(sha256_hex(&real.join("src")));
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(project_dir.join("logs.json"), "[]").unwrap();
+
+        assert!(find_hashed_project_dir(&scan(&link.join("repo"), cutoff), base.path()));
+    }
+
+    #[test]
+    fn test_find_hashed_project_dir_ignores_stale_activity() {
+        let base = tempfile::TempDir::new().unwrap();
+        let repo = tempfile::TempDir::new().unwrap();
+        let project_dir = base.path().join(sha256_hex(repo.path()));
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(project_dir.join("logs.json"), "[]").unwrap();
+
+        let future_cutoff = SystemTime::now() + std::time::Duration::from_secs(60);
+        assert!(!find_hashed_project_dir(&scan(repo.path(), future_cutoff), base.path()));
+    }
 }
@@ -0,0 +1,204 @@
+//! Breadcrumbs of AI plugins running inside JetBrains IDEs. The plugins live in the IDE's JVM,
+//! so no process ever matches an agent. Each IDE keeps per-product config and cache dirs
+//! (`~/.config/JetBrains/IntelliJIdea2025.1/`, `~/.cache/JetBrains/IntelliJIdea2025.1/`) in
+//! which plugins store per-project state, keyed by the project's name or its `ProjectId`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{Scan, has_recent_file, is_recent};
+use crate::user::{home_dir, xdg_dir};
+
+/// How deep to look for recent files inside a per-project plugin dir.
+const PROJECT_DIR_DEPTH: usize = 2;
+
+/// The JetBrains config and cache roots holding one dir per IDE product and version.
+pub(super) fn jetbrains_roots() -> Vec<PathBuf> {
+    if cfg!(target_os = "macos") {
+        let Some(library) = home_dir().map(|h| h.join("Library")) else {
+            return Vec::new();
+        };
+        vec![
+            library.join("Caches").join("JetBrains"),
+            library.join("Application Support").join("JetBrains"),
+        ]
+    } else {
+        [
+            xdg_dir("XDG_CACHE_HOME", ".cache"),
+            xdg_dir("XDG_CONFIG_HOME", ".config"),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join("JetBrains"))
+        .collect()
+    }
+}
+
+/// The `ProjectId` the IDE assigned to the project, from `.idea/workspace.xml`:
+/// `<component name="ProjectId" id="2abc..." />`.
+fn project_id(idea_dir: &Path) -> Option<String> {
+    let workspace = fs::read_to_string(idea_dir.join("workspace.xml")).ok()?;
+    let line = workspace.lines().find(|l| l.contains(r#"name="ProjectId""#))?;
+    let id = line.split(r#"id=""#).nth(1)?.split('"').next()?;
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+/// Whether a per-project dir name refers to this project: it contains the `ProjectId`, or is
+/// the project name optionally followed by `.<hash>` as in the IDE's `projects/` dirs.
+fn is_project_dir_name(name: &str, project_name: &str, project_id: Option<&str>) -> bool {
+    project_id.is_some_and(|id| name.contains(id))
+        || name == project_name
+        || name
+            .strip_prefix(project_name)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Per-project state of a plugin inside one IDE product dir, in either layout:
+/// `<product>/<plugin>/<project>` (a dir or a single file) or `<product>/projects/<project>/<plugin>/`.
+fn plugin_project_dirs(product: &Path, plugin: &str, project_name: &str, project_id: Option<&str>) -> Vec<PathBuf> {
+    let entries = |dir: PathBuf| {
+        fs::read_dir(dir).into_iter().flatten().flatten().filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| is_project_dir_name(name, project_name, project_id))
+        })
+    };
+    entries(product.join(plugin))
+        .map(|entry| entry.path())
+        .chain(entries(product.join("projects")).map(|entry| entry.path().join(plugin)))
+        .collect()
+}
+
+fn check_roots(scan: &Scan, roots: &[PathBuf], plugin_dirs: &[&str]) -> bool {
+    let idea_dir = scan.repo_path.join(".idea");
+    if !idea_dir.is_dir() {
+        if scan.debug {
+            eprintln!("    No {}", idea_dir.display());
+        }
+        return false;
+    }
+
+    // The IDE names the project after the dir it was opened as, which may be a symlink.
+    let mut project_names: Vec<String> = Vec::new();
+    for alias in scan.matcher.aliases() {
+        if let Some(name) = alias.file_name().and_then(|n| n.to_str())
+            && !project_names.iter().any(|n| n == name)
+        {
+            project_names.push(name.to_string());
+        }
+    }
+    let project_id = project_id(&idea_dir);
+    roots
+        .iter()
+        .flat_map(|root| fs::read_dir(root).into_iter().flatten().flatten())
+        .map(|product| product.path())
+        .any(|product| {
+            plugin_dirs.iter().any(|plugin| {
+                project_names
+                    .iter()
+                    .flat_map(|name| plugin_project_dirs(&product, plugin, name, project_id.as_deref()))
+                    .collect::<Vec<_>>()
+                    .iter()
+                    .any(|dir| {
+                        let recent = if dir.is_file() {
+                            is_recent(dir, scan.cutoff)
+                        } else {
+                            has_recent_file(dir, scan.cutoff, PROJECT_DIR_DEPTH)
+                        };
+                        if scan.debug {
+                            eprintln!("    {} recent: {}", dir.display(), recent);
+                        }
+                        recent && scan.found(dir)
+                    })
+            })
+        })
+}
+
+/// Recent plugin state for this project: a recent file in a per-project dir of one of
+/// `plugin_dirs`. Only repositories opened in a JetBrains IDE (those with `.idea/`) are
+/// considered. Plugin dirs in the repository itself (`.junie/`, `.aiassistant/`) are
+/// committed, and a pull bumps their mtime, so they aren't looked at.
+pub(super) fn check_ide_state(scan: &Scan, plugin_dirs: &[&str]) -> bool {
+    check_roots(scan, &jetbrains_roots(), plugin_dirs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::breadcrumbs::tests::recent_scan;
+
+    fn idea_project(parent: &Path, name: &str, id: &str) -> PathBuf {
+        let repo = parent.join(name);
+        fs::create_dir_all(repo.join(".idea")).unwrap();
+        fs::write(
+            repo.join(".idea").join("workspace.xml"),
+            format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<project version=\"4\">\n  <component name=\"ProjectId\" id=\"{}\" />\n</project>\n",
+                id
+            ),
+        )
+        .unwrap();
+        repo
+    }
+
+    #[test]
+    fn test_project_id() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = idea_project(dir.path(), "myrepo", "2abcDEF");
+        assert_eq!(project_id(&repo.join(".idea")).as_deref(), Some("2abcDEF"));
+        assert_eq!(project_id(dir.path()), None);
+    }
+
+    #[test]
+    fn test_is_project_dir_name() {
+        assert!(is_project_dir_name("myrepo.3f2a1b", "myrepo", None));
+        assert!(is_project_dir_name("myrepo", "myrepo", None));
+        assert!(is_project_dir_name("chat-2abcDEF.xml", "myrepo", Some("2abcDEF")));
+        assert!(!is_project_dir_name("myrepo2.3f2a1b", "myrepo", None));
+        assert!(!is_project_dir_name("other", "myrepo", Some("2abcDEF")));
+    }
+
+    #[test]
+    fn test_plugin_state_in_projects_dir() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = idea_project(dir.path(), "myrepo", "2abcDEF");
+        let root = dir.path().join("cache").join("JetBrains");
+        let state = root
+            .join("IntelliJIdea2025.1")
+            .join("projects")
+            .join("myrepo.3f2a1b")
+            .join("junie");
+        fs::create_dir_all(&state).unwrap();
+        let roots = [root];
+
+        assert!(!check_roots(&recent_scan(&repo), &roots, &["junie"]));
+        fs::write(state.join("session.json"), "{}").unwrap();
+        assert!(check_roots(&recent_scan(&repo), &roots, &["junie"]));
+        assert!(!check_roots(&recent_scan(&repo), &roots, &["aia"]));
+    }
+
+    #[test]
+    fn test_plugin_state_keyed_by_project_id() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = idea_project(dir.path(), "myrepo", "2abcDEF");
+        let root = dir.path().join("config").join("JetBrains");
+        let state = root.join("PyCharm2025.1").join("aia").join("2abcDEF");
+        fs::create_dir_all(&state).unwrap();
+        fs::write(state.join("chat.xml"), "<chats/>").unwrap();
+
+        assert!(check_roots(&recent_scan(&repo), &[root], &["aia"]));
+    }
+
+    #[test]
+    fn test_repo_plugin_dir_is_not_state() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = idea_project(dir.path(), "myrepo", "2abcDEF");
+        // Committed guidelines, freshly checked out.
+        fs::create_dir_all(repo.join(".junie")).unwrap();
+        fs::write(repo.join(".junie").join("guidelines.md"), "# Guidelines").unwrap();
+        let root = dir.path().join("cache").join("JetBrains");
+        fs::create_dir_all(root.join("IntelliJIdea2025.1")).unwrap();
+        assert!(!check_roots(&recent_scan(&repo), &[root], &["junie"]));
+    }
+}
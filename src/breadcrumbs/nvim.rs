@@ -0,0 +1,177 @@
+//! Chat history of Neovim AI plugins. The plugins run inside `nvim`, so only what they persist
+//! under Neovim's `stdpath()` dirs shows which project they were used in.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{Scan, cwd_matches_repo, has_extension, has_recent_file, is_recent};
+use crate::agent::{Agent, NvimHistory};
+use crate::user::xdg_dir;
+
+/// Fields codecompanion-history records the chat's directory in.
+const CHAT_CWD_KEYS: &[&str] = &["cwd", "project_root"];
+
+/// Neovim's `stdpath()` for an XDG base dir, honouring `$NVIM_APPNAME`.
+fn stdpath(var: &str, default: &str) -> Option<PathBuf> {
+    let app = std::env::var("NVIM_APPNAME")
+        .ok()
+        .filter(|a| !a.is_empty())
+        .unwrap_or_else(|| "nvim".to_string());
+    Some(xdg_dir(var, default)?.join(app))
+}
+
+/// avante.nvim's project dir name: '/' becomes "__", then anything but `[A-Za-z0-9._]` '_'.
+fn avante_dir_name(path: &Path) -> Option<String> {
+    let path = path.to_str()?.replace('/', "__");
+    Some(
+        path.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Whether avante has recent history for the repo, or for a project rooted in a subdirectory,
+/// under any of the repo's aliases.
+fn avante_has_history(scan: &Scan, projects: &Path) -> bool {
+    let names: Vec<String> = scan
+        .matcher
+        .aliases()
+        .iter()
+        .filter_map(|p| avante_dir_name(p))
+        .collect();
+    let Ok(entries) = fs::read_dir(projects) else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        let file_name = entry.file_name();
+        let Some(dir_name) = file_name.to_str() else {
+            return false;
+        };
+        let matches = |name: &String| {
+            dir_name == name
+                || dir_name
+                    .strip_prefix(name.as_str())
+                    .is_some_and(|r| r.starts_with("__"))
+        };
+        if !names.iter().any(matches) {
+            return false;
+        }
+        let recent = has_recent_file(&entry.path().join("history"), scan.cutoff, 1);
+        if scan.debug {
+            eprintln!("    {} recent: {}", entry.path().display(), recent);
+        }
+        recent && scan.found(&entry.path())
+    })
+}
+
+/// The directory a codecompanion-history chat was saved from. Chats are single-line JSON
+/// written by `vim.json.encode`, which escapes '/', so they're parsed rather than searched.
+fn read_chat_cwd(path: &Path) -> Option<String> {
+    let chat: serde_json::Value = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    CHAT_CWD_KEYS
+        .iter()
+        .find_map(|key| chat.get(*key)?.as_str().map(str::to_string))
+}
+
+fn codecompanion_has_chat(scan: &mut Scan, agent: &Agent, chats: &Path) -> bool {
+    let Ok(entries) = fs::read_dir(chats) else {
+        return false;
+    };
+    let addr = Agent::extract_email_addr(agent.email);
+    entries.flatten().map(|entry| entry.path()).any(|path| {
+        if !has_extension(&path, "json") || !is_recent(&path, scan.cutoff) {
+            return false;
+        }
+        let cwd = scan.cache.cwd(&path, addr, || read_chat_cwd(&path));
+        if scan.debug {
+            eprintln!("    {}: {:?}", path.display(), cwd);
+        }
+        cwd.is_some_and(|cwd| cwd_matches_repo(&cwd, &scan.matcher)) && scan.found(&path)
+    })
+}
+
+/// Where a plugin keeps its history.
+pub(super) fn history_dir(history: NvimHistory) -> Option<PathBuf> {
+    match history {
+        NvimHistory::Avante => stdpath("XDG_STATE_HOME", ".local/state").map(|d| d.join("avante").join("projects")),
+        NvimHistory::CodeCompanion => {
+            stdpath("XDG_DATA_HOME", ".local/share").map(|d| d.join("codecompanion-history").join("chats"))
+        }
+    }
+}
+
+pub(super) fn check_history(scan: &mut Scan, agent: &Agent, history: NvimHistory) -> bool {
+    let Some(dir) = history_dir(history) else {
+        return false;
+    };
+    if scan.debug {
+        eprintln!("    History dir: {}", dir.display());
+    }
+    match history {
+        NvimHistory::Avante => avante_has_history(scan, &dir),
+        NvimHistory::CodeCompanion => codecompanion_has_chat(scan, agent, &dir),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::breadcrumbs::tests::recent_scan;
+
+    #[test]
+    fn test_avante_dir_name() {
+        assert_eq!(
+            avante_dir_name(Path::new("/Users/foo/my-repo.rs")).as_deref(),
+            Some("__Users__foo__my_repo.rs")
+        );
+    }
+
+    #[test]
+    fn test_avante_history_for_repo_and_subdir() {
+        let projects = tempfile::TempDir::new().unwrap();
+        let history = projects.path().join("__Users__foo__myrepo__apps__web").join("history");
+        fs::create_dir_all(&history).unwrap();
+        fs::create_dir_all(projects.path().join("__Users__foo__myrepo2").join("history")).unwrap();
+        let repo = Path::new("/Users/foo/myrepo");
+        assert!(!avante_has_history(&recent_scan(repo), projects.path()));
+
+        fs::write(history.join("0.json"), r#"{"title":"untitled","entries":[]}"#).unwrap();
+        assert!(avante_has_history(&recent_scan(repo), projects.path()));
+        assert!(!avante_has_history(
+            &recent_scan(Path::new("/Users/foo/other")),
+            projects.path()
+        ));
+    }
+
+    #[test]
+    fn test_codecompanion_chat_cwd() {
+        let chats = tempfile::TempDir::new().unwrap();
+        fs::write(
+            chats.path().join("1718000000.json"),
+            r#"{"save_id":"1718000000","title":"Fix tests","cwd":"\/Users\/foo\/myrepo","messages":[]}"#,
+        )
+        .unwrap();
+        fs::write(chats.path().join("index.json"), "{}").unwrap();
+        let agent = crate::agent::KNOWN_AGENTS
+            .iter()
+            .find(|a| a.name == "codecompanion")
+            .unwrap();
+
+        assert!(codecompanion_has_chat(
+            &mut recent_scan(Path::new("/Users/foo/myrepo")),
+            agent,
+            chats.path()
+        ));
+        assert!(!codecompanion_has_chat(
+            &mut recent_scan(Path::new("/Users/foo/other")),
+            agent,
+            chats.path()
+        ));
+    }
+}
@@ -0,0 +1,351 @@
+//! Breadcrumbs kept by VS Code-family editors (VS Code, Cursor, Windsurf) and their
+//! extensions. These run inside Electron processes whose names never match an agent.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::{Scan, cwd_matches_repo, has_recent_file, is_recent};
+use crate::agent::WorkspaceActivity;
+use crate::paths::PathMatcher;
+use crate::user::{home_dir, xdg_dir};
+
+/// The `User` data dir of an editor, e.g. `~/.config/Cursor/User`.
+pub(super) fn user_dir(app: &str) -> Option<PathBuf> {
+    let base = if cfg!(target_os = "macos") {
+        home_dir()?.join("Library").join("Application Support")
+    } else {
+        xdg_dir("XDG_CONFIG_HOME", ".config")?
+    };
+    Some(base.join(app).join("User"))
+}
+
+/// Decode a `file://` URI from workspace.json into a path.
+fn file_uri_to_path(uri: &str) -> Option<String> {
+    let encoded = uri.strip_prefix("file://")?;
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = encoded.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Workspace storage dirs whose `workspace.json` folder is the repo or inside it.
+/// Multi-root `.code-workspace` workspaces aren't matched.
+fn matching_workspaces(storage: &Path, matcher: &PathMatcher, debug: bool) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(storage) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|dir| {
+            let Ok(content) = fs::read_to_string(dir.join("workspace.json")) else {
+                return false;
+            };
+            let folder = serde_json::from_str::<serde_json::Value>(&content)
+                .ok()
+                .and_then(|v| v.get("folder")?.as_str().and_then(file_uri_to_path));
+            let Some(folder) = folder else {
+                return false;
+            };
+            let matched = cwd_matches_repo(&folder, matcher);
+            if matched && debug {
+                eprintln!("    Workspace {} -> {}", dir.display(), folder);
+            }
+            matched
+        })
+        .collect()
+}
+
+/// Millisecond timestamps under any `lastUpdatedAt` key, as Cursor records per composer.
+fn latest_updated_at(value: &serde_json::Value) -> Option<u64> {
+    match value {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .filter_map(|(k, v)| match k.as_str() {
+                "lastUpdatedAt" => v.as_u64(),
+                _ => latest_updated_at(v),
+            })
+            .max(),
+        serde_json::Value::Array(items) => items.iter().filter_map(latest_updated_at).max(),
+        _ => None,
+    }
+}
+
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Check `state.vscdb` for AI state keys (SQL LIKE patterns) with an entry whose
+/// `lastUpdatedAt` is since the cutoff. Keys that are merely present only show the editor had
+/// the workspace open.
+fn state_db_has_activity(scan: &Scan, db: &Path, keys: &[&str]) -> bool {
+    if !is_recent(db, scan.cutoff) && !is_recent(&db.with_file_name("state.vscdb-wal"), scan.cutoff) {
+        return false;
+    }
+    let result = (|| -> rusqlite::Result<Vec<String>> {
+        let conn = rusqlite::Connection::open_with_flags(db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        conn.busy_timeout(std::time::Duration::from_millis(100))?;
+        let mut stmt = conn.prepare("SELECT CAST(value AS TEXT) FROM ItemTable WHERE key LIKE ?1")?;
+        let mut values = Vec::new();
+        for key in keys {
+            for value in stmt.query_map([key], |row| row.get::<_, String>(0))? {
+                values.push(value?);
+            }
+        }
+        Ok(values)
+    })();
+
+    let values = match result {
+        Ok(values) => values,
+        Err(e) => {
+            if scan.debug {
+                eprintln!("    Failed to query {}: {}", db.display(), e);
+            }
+            return false;
+        }
+    };
+    let cutoff_ms = millis(scan.cutoff);
+    values.iter().any(|value| {
+        serde_json::from_str::<serde_json::Value>(value)
+            .ok()
+            .as_ref()
+            .and_then(latest_updated_at)
+            .is_some_and(|updated| updated >= cutoff_ms)
+    })
+}
+
+fn workspace_has_activity(scan: &Scan, workspace: &Path, activity: &WorkspaceActivity) -> bool {
+    match activity {
+        WorkspaceActivity::Files(dirs) => dirs.iter().any(|d| has_recent_file(&workspace.join(d), scan.cutoff, 1)),
+        WorkspaceActivity::StateKeys(keys) => state_db_has_activity(scan, &workspace.join("state.vscdb"), keys),
+    }
+}
+
+pub(super) fn check_workspaces(scan: &Scan, apps: &[&str], activity: &WorkspaceActivity) -> bool {
+    apps.iter().filter_map(|app| user_dir(app)).any(|user| {
+        let storage = user.join("workspaceStorage");
+        if scan.debug {
+            eprintln!("    Workspace storage: {}", storage.display());
+        }
+        matching_workspaces(&storage, &scan.matcher, scan.debug)
+            .iter()
+            .any(|workspace| workspace_has_activity(scan, workspace, activity) && scan.found(workspace))
+    })
+}
+
+/// Fields Cline (`cwdOnTaskInitialization`) and its Roo/Kilo forks (`workspace`) use to record
+/// where a task ran.
+const TASK_CWD_KEYS: &[&str] = &["cwdOnTaskInitialization", "workspace"];
+
+/// Whether a task history (a JSON array of tasks with a millisecond `ts`) has a task
+/// since the cutoff in this repo.
+fn task_history_matches(scan: &Scan, history: &serde_json::Value) -> bool {
+    let cutoff_ms = millis(scan.cutoff);
+    history.as_array().is_some_and(|tasks| {
+        tasks.iter().any(|task| {
+            let recent = task
+                .get("ts")
+                .and_then(|ts| ts.as_u64())
+                .is_some_and(|ts| ts >= cutoff_ms);
+            recent
+                && TASK_CWD_KEYS
+                    .iter()
+                    .filter_map(|key| task.get(*key)?.as_str())
+                    .any(|cwd| cwd_matches_repo(cwd, &scan.matcher))
+        })
+    })
+}
+
+/// Task history of a Cline-style extension: `globalStorage/<extension>/state/taskHistory.json`
+/// in current versions, or the `taskHistory` entry of the extension's global state in
+/// `globalStorage/state.vscdb` in older ones.
+pub(super) fn check_task_history(scan: &Scan, apps: &[&str], extension: &str) -> bool {
+    apps.iter().filter_map(|app| user_dir(app)).any(|user| {
+        let global = user.join("globalStorage");
+        let history_file = global.join(extension).join("state").join("taskHistory.json");
+        if scan.debug {
+            eprintln!("    Task history: {}", history_file.display());
+        }
+        if is_recent(&history_file, scan.cutoff)
+            && let Ok(content) = fs::read_to_string(&history_file)
+            && let Ok(history) = serde_json::from_str::<serde_json::Value>(&content)
+        {
+            return task_history_matches(scan, &history) && scan.found(&history_file);
+        }
+
+        let db = global.join("state.vscdb");
+        if !is_recent(&db, scan.cutoff) {
+            return false;
+        }
+        let state =
+            rusqlite::Connection::open_with_flags(&db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY).and_then(|conn| {
+                conn.query_row(
+                    "SELECT CAST(value AS TEXT) FROM ItemTable WHERE key = ?1",
+                    [extension],
+                    |row| row.get::<_, String>(0),
+                )
+            });
+        state
+            .ok()
+            .and_then(|state| serde_json::from_str::<serde_json::Value>(&state).ok())
+            .is_some_and(|state| state.get("taskHistory").is_some_and(|h| task_history_matches(scan, h)))
+            && scan.found(&db)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::breadcrumbs::tests::recent_scan;
+
+    #[test]
+    fn test_file_uri_to_path() {
+        assert_eq!(
+            file_uri_to_path("file:///Users/foo/my%20repo").as_deref(),
+            Some("/Users/foo/my repo")
+        );
+        assert_eq!(file_uri_to_path("vscode-remote://ssh-remote+box/src"), None);
+    }
+
+    #[test]
+    fn test_matching_workspaces() {
+        let storage = tempfile::TempDir::new().unwrap();
+        for (id, folder) in [
+            ("a1", "file:///Users/foo/myrepo"),
+            ("b2", "file:///Users/foo/myrepo/apps/web"),
+            ("c3", "file:///Users/foo/myrepo2"),
+        ] {
+            let dir = storage.path().join(id);
+            fs::create_dir(&dir).unwrap();
+            fs::write(dir.join("workspace.json"), format!(r#"{{"folder":"{}"}}"#, folder)).unwrap();
+        }
+
+        let mut found = matching_workspaces(
+            storage.path(),
+            &PathMatcher::new(&[PathBuf::from("/Users/foo/myrepo")], &[]),
+            false,
+        );
+        found.sort();
+        assert_eq!(found, vec![storage.path().join("a1"), storage.path().join("b2")]);
+    }
+
+    #[test]
+    fn test_workspace_chat_sessions_activity() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let repo = Path::new("/Users/foo/myrepo");
+        let activity = WorkspaceActivity::Files(&["chatSessions"]);
+        assert!(!workspace_has_activity(&recent_scan(repo), workspace.path(), &activity));
+
+        fs::create_dir(workspace.path().join("chatSessions")).unwrap();
+        fs::write(workspace.path().join("chatSessions").join("abc.json"), "{}").unwrap();
+        assert!(workspace_has_activity(&recent_scan(repo), workspace.path(), &activity));
+    }
+
+    fn state_db(path: &Path, rows: &[(&str, String)]) {
+        let conn = rusqlite::Connection::open(path).unwrap();
+        conn.execute_batch("CREATE TABLE ItemTable (key TEXT UNIQUE ON CONFLICT REPLACE, value BLOB)")
+            .unwrap();
+        for (key, value) in rows {
+            conn.execute("INSERT INTO ItemTable (key, value) VALUES (?1, ?2)", (key, value))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_cursor_composer_activity() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = dir.path().join("state.vscdb");
+        let now = millis(SystemTime::now());
+        state_db(
+            &db,
+            &[(
+                "composer.composerData",
+                format!(
+                    r#"{{"allComposers":[{{"composerId":"x","lastUpdatedAt":{}}},{{"composerId":"y","lastUpdatedAt":1}}]}}"#,
+                    now
+                ),
+            )],
+        );
+        let repo = Path::new("/Users/foo/myrepo");
+        assert!(state_db_has_activity(
+            &recent_scan(repo),
+            &db,
+            &["composer.composerData"]
+        ));
+        assert!(!state_db_has_activity(&recent_scan(repo), &db, &["aiService.prompts"]));
+
+        // Present, but with nothing saying when it was used.
+        let prompts = dir.path().join("prompts.vscdb");
+        state_db(
+            &prompts,
+            &[("aiService.prompts", r#"[{"text":"hi","commandType":4}]"#.to_string())],
+        );
+        assert!(!state_db_has_activity(
+            &recent_scan(repo),
+            &prompts,
+            &["aiService.prompts"]
+        ));
+    }
+
+    #[test]
+    fn test_cursor_composer_stale() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = dir.path().join("state.vscdb");
+        state_db(
+            &db,
+            &[(
+                "composer.composerData",
+                r#"{"allComposers":[{"composerId":"x","lastUpdatedAt":1000}]}"#.to_string(),
+            )],
+        );
+        assert!(!state_db_has_activity(
+            &recent_scan(Path::new("/Users/foo/myrepo")),
+            &db,
+            &["composer.composerData"]
+        ));
+    }
+
+    #[test]
+    fn test_task_history_matches() {
+        let now = millis(SystemTime::now());
+        let history: serde_json::Value = serde_json::from_str(&format!(
+            r#"[
+                {{"id":"1","ts":{now},"task":"fix","cwdOnTaskInitialization":"/Users/foo/myrepo"}},
+                {{"id":"2","ts":{now},"task":"roo","workspace":"/Users/foo/roo-repo"}},
+                {{"id":"3","ts":1000,"task":"old","cwdOnTaskInitialization":"/Users/foo/old"}}
+            ]"#
+        ))
+        .unwrap();
+
+        assert!(task_history_matches(
+            &recent_scan(Path::new("/Users/foo/myrepo")),
+            &history
+        ));
+        assert!(task_history_matches(
+            &recent_scan(Path::new("/Users/foo/roo-repo")),
+            &history
+        ));
+        assert!(!task_history_matches(
+            &recent_scan(Path::new("/Users/foo/old")),
+            &history
+        ));
+        assert!(!task_history_matches(
+            &recent_scan(Path::new("/Users/foo/other")),
+            &history
+        ));
+    }
+}
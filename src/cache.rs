@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::user::xdg_dir;
+
+const HEADER: &str = "# aittributor session cache v1";
+
+/// Entries not looked up for this long are dropped when the cache is saved.
+const PRUNE_AGE_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// What was extracted from a session file, valid while its mtime and size are unchanged.
+#[derive(Clone, Debug, PartialEq)]
+struct Entry {
+    mtime_nanos: u128,
+    size: u64,
+    /// Unix time this entry was last looked up, used for pruning.
+    seen: u64,
+    agent: String,
+    cwd: Option<String>,
+}
+
+/// Persistent index of session files to their recorded cwd, so repeated commits don't
+/// re-open every recent session file. Stored as tab-separated lines under
+/// `$XDG_CACHE_HOME/aittributor/`.
+#[derive(Default)]
+pub struct SessionCache {
+    path: Option<PathBuf>,
+    entries: HashMap<PathBuf, Entry>,
+    dirty: bool,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn file_key(path: &Path) -> Option<(u128, u64)> {
+    let meta = path.metadata().ok()?;
+    let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((mtime.as_nanos(), meta.len()))
+}
+
+impl SessionCache {
+    pub fn default_path() -> Option<PathBuf> {
+        Some(
+            xdg_dir("XDG_CACHE_HOME", ".cache")?
+                .join("aittributor")
+                .join("sessions.tsv"),
+        )
+    }
+
+    /// Load the cache from `path`. A missing or unreadable cache starts empty.
+    pub fn load(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .filter(|content| content.starts_with(HEADER))
+            .map(|content| content.lines().skip(1).filter_map(parse_line).collect())
+            .unwrap_or_default();
+        SessionCache {
+            path: Some(path),
+            entries,
+            dirty: false,
+        }
+    }
+
+    /// The cwd recorded in `path`, reading it with `read` only when the cached entry is
+    /// missing or the file has changed since it was cached.
+    pub fn cwd(&mut self, path: &Path, agent: &str, read: impl FnOnce() -> Option<String>) -> Option<String> {
+        let Some((mtime_nanos, size)) = file_key(path) else {
+            return read();
+        };
+        let now = now_secs();
+        if let Some(entry) = self.entries.get_mut(path)
+            && entry.mtime_nanos == mtime_nanos
+            && entry.size == size
+        {
+            if now.saturating_sub(entry.seen) > 60 * 60 {
+                entry.seen = now;
+                self.dirty = true;
+            }
+            return entry.cwd.clone();
+        }
+
+        let cwd = read();
+        if is_storable(path, cwd.as_deref()) {
+            self.entries.insert(
+                path.to_path_buf(),
+                Entry {
+                    mtime_nanos,
+                    size,
+                    seen: now,
+                    agent: agent.to_string(),
+                    cwd: cwd.clone(),
+                },
+            );
+            self.dirty = true;
+        }
+        cwd
+    }
+
+    /// Write the cache back if anything changed, dropping stale entries. Written to a temp
+    /// file and renamed so concurrent commits never see a partial cache.
+    pub fn save(&mut self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+        let cutoff = now_secs().saturating_sub(PRUNE_AGE_SECS);
+        self.entries.retain(|_, entry| entry.seen >= cutoff);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension(format!("tmp.{}", std::process::id()));
+        let mut file = std::io::BufWriter::new(fs::File::create(&tmp)?);
+        writeln!(file, "{}", HEADER)?;
+        for (session, entry) in &self.entries {
+            writeln!(
+                file,
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                session.display(),
+                entry.mtime_nanos,
+                entry.size,
+                entry.seen,
+                entry.agent,
+                entry.cwd.as_deref().unwrap_or("")
+            )?;
+        }
+        // Not synced: a cache lost in a crash is rebuilt from the session files.
+        file.flush()?;
+        fs::rename(&tmp, path)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// Tabs and newlines would break the line format; such files are simply not cached.
+fn is_storable(path: &Path, cwd: Option<&str>) -> bool {
+    let bad = |s: &str| s.contains(['\t', '\n', '\r']);
+    path.to_str().is_some_and(|p| !bad(p)) && !cwd.is_some_and(bad)
+}
+
+fn parse_line(line: &str) -> Option<(PathBuf, Entry)> {
+    let mut fields = line.splitn(6, '\t');
+    let path = PathBuf::from(fields.next()?);
+    let entry = Entry {
+        mtime_nanos: fields.next()?.parse().ok()?,
+        size: fields.next()?.parse().ok()?,
+        seen: fields.next()?.parse().ok()?,
+        agent: fields.next()?.to_string(),
+        cwd: Some(fields.next()?).filter(|c| !c.is_empty()).map(str::to_string),
+    };
+    Some((path, entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_reads_once_until_file_changes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let session = dir.path().join("session.jsonl");
+        fs::write(&session, "{}\n").unwrap();
+        let mut cache = SessionCache::default();
+
+        let reads = std::cell::Cell::new(0);
+        let lookup = |cache: &mut SessionCache| {
+            cache.cwd(&session, "noreply@anthropic.com", || {
+                reads.set(reads.get() + 1);
+                Some("/Users/foo/myrepo".to_string())
+            })
+        };
+        assert_eq!(lookup(&mut cache).as_deref(), Some("/Users/foo/myrepo"));
+        assert_eq!(lookup(&mut cache).as_deref(), Some("/Users/foo/myrepo"));
+        assert_eq!(reads.get(), 1);
+
+        fs::write(&session, "{}\n{}\n").unwrap();
+        lookup(&mut cache);
+        assert_eq!(reads.get(), 2);
+    }
+
+    #[test]
+    fn test_cache_round_trips_through_disk() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cache_path = dir.path().join("cache").join("sessions.tsv");
+        let session = dir.path().join("session.jsonl");
+        fs::write(&session, "{}\n").unwrap();
+
+        let mut cache = SessionCache::load(cache_path.clone());
+        cache.cwd(&session, "noreply@openai.com", || Some("/Users/foo/myrepo".to_string()));
+        cache.cwd(&dir.path().join("missing.jsonl"), "noreply@openai.com", || None);
+        cache.save().unwrap();
+
+        let mut reloaded = SessionCache::load(cache_path);
+        assert_eq!(reloaded.entries.len(), 1);
+        let cwd = reloaded.cwd(&session, "noreply@openai.com", || panic!("should be cached"));
+        assert_eq!(cwd.as_deref(), Some("/Users/foo/myrepo"));
+    }
+
+    #[test]
+    fn test_cache_prunes_stale_entries() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cache_path = dir.path().join("sessions.tsv");
+        let stale_seen = now_secs() - PRUNE_AGE_SECS - 1;
+        fs::write(
+            &cache_path,
+            format!(
+                "{}\n/old.jsonl\t1\t2\t{}\tnoreply@openai.com\t/repo\n",
+                HEADER, stale_seen
+            ),
+        )
+        .unwrap();
+
+        let mut cache = SessionCache::load(cache_path.clone());
+        assert_eq!(cache.entries.len(), 1);
+        cache.dirty = true;
+        cache.save().unwrap();
+        assert!(SessionCache::load(cache_path).entries.is_empty());
+    }
+
+    #[test]
+    fn test_parse_line_without_cwd() {
+        let (path, entry) = parse_line("/s.jsonl\t10\t20\t30\tamp@ampcode.com\t").unwrap();
+        assert_eq!(path, PathBuf::from("/s.jsonl"));
+        assert_eq!(entry.cwd, None);
+        assert_eq!(entry.agent, "amp@ampcode.com");
+    }
+}
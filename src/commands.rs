@@ -0,0 +1,125 @@
+use std::path::PathBuf;
+
+use clap::Subcommand;
+
+use crate::agent::Agent;
+use crate::config::Config;
+
+mod agents;
+mod doctor;
+mod explain;
+mod filter;
+mod integrate;
+mod log;
+mod mark;
+mod status;
+mod watch;
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// List known agents, how each is detected, and where their breadcrumbs live on this machine
+    Agents {
+        /// Explain whether the named agent would be detected in the current repository right now
+        #[arg(long, value_name = "AGENT")]
+        check: Option<String>,
+    },
+    /// Check the hook installation and environment, and suggest fixes
+    Doctor,
+    /// Show why each agent in a commit was credited, from the evidence stored when it was made
+    Explain {
+        #[arg(default_value = "HEAD")]
+        commit: String,
+    },
+    /// Add trailers for the detected agents to a commit message read on stdin, writing it to
+    /// stdout. For tools that build the message themselves and run `git commit -F -`
+    Filter {
+        /// Enable debug output
+        #[arg(long)]
+        debug: bool,
+    },
+    /// Configure an agent's hooks to report its edits with `aittributor mark`
+    Integrate {
+        #[arg(value_enum)]
+        agent: integrate::Integration,
+        /// Show the change to the agent's config instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show the audit log of hook runs: the agents found, how, and the trailers added
+    Log {
+        /// Show runs in every repository, not just the current one
+        #[arg(long)]
+        all: bool,
+        /// Only show runs that found this agent (name or part of its email)
+        #[arg(long)]
+        agent: Option<String>,
+        /// Show at most this many of the latest runs
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+        /// Print the records as JSON lines
+        #[arg(long)]
+        json: bool,
+    },
+    /// Record that an agent edited files, so commits including them credit it
+    Mark {
+        /// Agent name or email, e.g. "claude"
+        #[arg(long)]
+        agent: String,
+        /// Session id to record with the edit
+        #[arg(long)]
+        session: Option<String>,
+        /// Read an agent hook's JSON payload from stdin
+        #[arg(long, conflicts_with = "payload")]
+        from_hook: bool,
+        /// An agent hook's JSON payload, for hook systems that pass it as an argument
+        #[arg(long, value_name = "JSON")]
+        payload: Option<String>,
+        /// Edited files. Defaults to those named in the payload, or else all uncommitted changes
+        files: Vec<PathBuf>,
+    },
+    /// Store the evidence behind the trailers of the commit just made, as a git note. Run from
+    /// the post-commit hook
+    PostCommit,
+    /// Show which config rule applies to the current repository, and the resulting settings
+    Status,
+    /// Record which repositories agents work in until killed, for commits made after they exit
+    Watch {
+        /// Seconds between samples of the process table
+        #[arg(long, value_name = "SECS", default_value_t = 5)]
+        interval: u64,
+    },
+}
+
+/// Run a subcommand. Unlike the hook, subcommands are run by hand and aren't bound by its
+/// time budget.
+pub fn run(command: Command) -> Result<(), String> {
+    let config = Config::load();
+    Agent::register(&config.agents);
+    match command {
+        Command::Agents { check: None } => {
+            agents::list();
+            Ok(())
+        }
+        Command::Agents { check: Some(name) } => agents::check(&name, &config),
+        Command::Doctor => doctor::run(&config),
+        Command::Explain { commit } => explain::run(&commit),
+        Command::Filter { debug } => filter::run(config, debug),
+        Command::Integrate { agent, dry_run } => integrate::run(agent, dry_run),
+        Command::Log {
+            all,
+            agent,
+            limit,
+            json,
+        } => log::run(all, agent.as_deref(), limit, json),
+        Command::Mark {
+            agent,
+            session,
+            from_hook,
+            payload,
+            files,
+        } => mark::run(&config, &agent, session, from_hook, payload, files),
+        Command::PostCommit => crate::notes::attach_pending(),
+        Command::Status => status::run(&config),
+        Command::Watch { interval } => watch::run(&config, interval),
+    }
+}
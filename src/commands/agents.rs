@@ -0,0 +1,169 @@
+use crate::agent::{Agent, BreadcrumbLookup, NvimHistory};
+use crate::breadcrumbs;
+use crate::config::Config;
+use crate::paths::PathMatcher;
+use crate::process::ProcessTable;
+use crate::user;
+
+fn describe_lookup(agent: &Agent) -> Option<String> {
+    let ext = agent.breadcrumb_ext.unwrap_or("jsonl");
+    let has_dir =
+        agent.breadcrumb_dir.is_some() || !agent.breadcrumb_env_dirs.is_empty() || agent.breadcrumb_xdg_dir.is_some();
+    let method = match agent.breadcrumb_lookup {
+        BreadcrumbLookup::Walk if has_dir => format!("session files (*.{})", ext),
+        BreadcrumbLookup::Walk => return None,
+        BreadcrumbLookup::DashedProjectDir => format!("per-project session dirs (*.{})", ext),
+        BreadcrumbLookup::HashedProjectDir => "per-project dirs named by path hash".to_string(),
+        BreadcrumbLookup::InRepo(patterns) => format!("files in the repository: {}", patterns.join(", ")),
+        BreadcrumbLookup::VsCodeWorkspace { apps, .. } => format!("workspace storage of {}", apps.join(", ")),
+        BreadcrumbLookup::VsCodeTaskHistory { extension, .. } => format!("task history of {}", extension),
+        BreadcrumbLookup::JetBrains { plugin_dirs } => format!("JetBrains plugin state ({})", plugin_dirs.join(", ")),
+        BreadcrumbLookup::Nvim(NvimHistory::Avante) => "avante.nvim history".to_string(),
+        BreadcrumbLookup::Nvim(NvimHistory::CodeCompanion) => "codecompanion-history chats".to_string(),
+    };
+    Some(match &agent.breadcrumb_sqlite {
+        Some(source) => format!("{}, SQLite {}", method, source.file),
+        None => method,
+    })
+}
+
+fn describe_processes(agent: &Agent) -> Option<String> {
+    if agent.process_names.is_empty() {
+        return None;
+    }
+    let mut description = agent.process_names.join(", ");
+    if agent.exact_process_match {
+        description.push_str(" (exact)");
+    }
+    if !agent.host_processes.is_empty() {
+        description.push_str(&format!(" under {}", agent.host_processes.join(", ")));
+    }
+    Some(description)
+}
+
+fn describe_env(agent: &Agent) -> Option<String> {
+    (!agent.env_vars.is_empty()).then(|| {
+        agent
+            .env_vars
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(", ")
+    })
+}
+
+fn print_agent(agent: &Agent, heading: &str) {
+    println!("{}", heading);
+    if let Some(processes) = describe_processes(agent) {
+        println!("  process:     {}", processes);
+    }
+    if let Some(env) = describe_env(agent) {
+        println!("  env:         {}", env);
+    }
+    if let Some(lookup) = describe_lookup(agent) {
+        println!("  breadcrumbs: {}", lookup);
+    }
+    for source in breadcrumbs::sources(agent) {
+        let state = match (source.path.exists(), source.recent) {
+            (false, _) => "missing".to_string(),
+            (true, Some(recent)) => format!("exists, {} recent", recent),
+            (true, None) => "exists".to_string(),
+        };
+        println!("    {} ({})", source.path.display(), state);
+    }
+    println!();
+}
+
+pub fn list() {
+    for agent in Agent::all() {
+        let configured = if agent.is_configured() { " (configured)" } else { "" };
+        print_agent(agent, &format!("{}{}", agent.email, configured));
+    }
+    let mut unnamed = Agent::without_email().peekable();
+    if unnamed.peek().is_none() {
+        return;
+    }
+    println!("Needs configuration: no co-author email is known for these, so they aren't detected until an");
+    println!("[[agents]] entry with their name gives them one.\n");
+    for agent in unnamed {
+        print_agent(agent, &format!("{} (needs configuration)", agent.name));
+    }
+}
+
+pub fn check(name: &str, config: &Config) -> Result<(), String> {
+    let agent = Agent::find_by_name_or_email(name).ok_or_else(|| format!("unknown agent: {}", name))?;
+    let repo_path = crate::current_repo_path().ok_or("cannot determine the current directory")?;
+    println!("{} in {}", agent.email, repo_path.display());
+
+    println!("\nEnvironment:");
+    if agent.env_vars.is_empty() {
+        println!("  no environment signature");
+    }
+    for (key, value) in agent.env_vars {
+        let current = std::env::var(key).ok();
+        let verdict = if current.as_deref() == Some(*value) {
+            "match"
+        } else {
+            "no match"
+        };
+        println!("  {}={} (currently {:?}): {}", key, value, current, verdict);
+    }
+
+    println!("\nProcesses:");
+    let table = ProcessTable::load();
+    let owner = (!config.include_other_users).then(user::current_uid);
+    let roots = crate::repo_roots(config).unwrap_or_default();
+    let matcher = PathMatcher::new(&roots, &config.path_map).with_mount_info();
+    let mut found = false;
+    for process in table.iter() {
+        if !Agent::is_candidate(process)
+            || !Agent::find_for_process(process, false).is_some_and(|a| std::ptr::eq(a, agent))
+        {
+            continue;
+        }
+        found = true;
+        let cwd = crate::agent_cwd(&table, process, agent, false);
+        let verdict = match cwd {
+            None if agent.host_processes.is_empty() => "cwd unreadable",
+            None => "not under a host editor",
+            Some(cwd) if !matcher.matches_process(cwd, process.pid) => "outside the repository",
+            Some(_) if owner.is_some() && process.uid != owner => "owned by another user",
+            Some(_) => "in the repository",
+        };
+        println!(
+            "  PID {} {} cwd {}: {}",
+            process.pid,
+            process.name,
+            cwd.map_or("?".to_string(), |c| c.display().to_string()),
+            verdict
+        );
+    }
+    if !found {
+        println!("  none running");
+    }
+    // Only processes in the commit's own ancestry or alongside it are attributed.
+    let detected = crate::detect_agents(config, false)
+        .candidates
+        .iter()
+        .any(|c| std::ptr::eq(c.agent, agent));
+
+    println!("\nBreadcrumbs (trace on stderr):");
+    let from_breadcrumbs = match describe_lookup(agent) {
+        Some(lookup) => {
+            println!("  {}", lookup);
+            breadcrumbs::explain_agent(agent, &repo_path, config)
+        }
+        None => {
+            println!("  none kept");
+            false
+        }
+    };
+
+    println!();
+    match (detected, from_breadcrumbs) {
+        (true, _) => println!("Detected from the environment or a related process."),
+        (false, true) => println!("Detected from breadcrumbs."),
+        (false, false) => println!("Not detected."),
+    }
+    Ok(())
+}
@@ -0,0 +1,322 @@
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::HOOK_BUDGET;
+use crate::agent::Agent;
+use crate::audit;
+use crate::breadcrumbs;
+use crate::cache::SessionCache;
+use crate::config::Config;
+use crate::git::git_stdout;
+use crate::paths::PathMatcher;
+use crate::process::ProcessTable;
+use crate::user;
+
+/// Results of the checks, printed as they run.
+#[derive(Default)]
+struct Report {
+    failures: usize,
+}
+
+impl Report {
+    fn ok(&mut self, message: impl std::fmt::Display) {
+        println!("✓ {}", message);
+    }
+
+    fn warn(&mut self, message: impl std::fmt::Display, fix: impl std::fmt::Display) {
+        println!("! {}", message);
+        println!("  fix: {}", fix);
+    }
+
+    fn fail(&mut self, message: impl std::fmt::Display, fix: impl std::fmt::Display) {
+        self.failures += 1;
+        println!("✗ {}", message);
+        println!("  fix: {}", fix);
+    }
+}
+
+fn check_config(report: &mut Report) {
+    let Some(path) = Config::path() else {
+        report.warn(
+            "No config location: neither $HOME nor $XDG_CONFIG_HOME is set",
+            "set AITTRIBUTOR_CONFIG to use a config file",
+        );
+        return;
+    };
+    if !path.exists() {
+        report.ok(format!("No config file at {} (using defaults)", path.display()));
+        return;
+    }
+    match Config::load_from(&path) {
+        Ok(config) => report.ok(format!(
+            "Config {} is valid ({} configured agent(s))",
+            path.display(),
+            config.agents.len()
+        )),
+        Err(e) => report.fail(
+            format!(
+                "Config {} is invalid and is being ignored: {}",
+                path.display(),
+                e.trim()
+            ),
+            "fix the file, or remove it to use the defaults",
+        ),
+    }
+}
+
+/// Whether a hook runs aittributor: it is (a link to) the binary, or a script mentioning it.
+fn hook_runs_aittributor(hook: &Path) -> bool {
+    let target = fs::canonicalize(hook).unwrap_or_else(|_| hook.to_path_buf());
+    target
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.contains("aittributor"))
+        || fs::read(hook).is_ok_and(|content| content.windows(11).any(|w| w == b"aittributor"))
+}
+
+fn check_hook(report: &mut Report) {
+    if let Some(hooks_path) = git_stdout(&["config", "core.hooksPath"]) {
+        report.ok(format!("core.hooksPath is set to {}", hooks_path));
+    }
+    let Some(hooks_dir) = git_stdout(&["rev-parse", "--git-path", "hooks"]) else {
+        report.fail(
+            "Not in a git repository",
+            "run `aittributor doctor` from the repository you commit to",
+        );
+        return;
+    };
+    let hook = PathBuf::from(hooks_dir).join("prepare-commit-msg");
+
+    let Ok(meta) = fs::metadata(&hook) else {
+        report.fail(
+            format!("No prepare-commit-msg hook at {}", hook.display()),
+            format!(
+                "ln -s \"$(command -v aittributor)\" {} (or add it to your hook manager, see README)",
+                hook.display()
+            ),
+        );
+        return;
+    };
+    if meta.permissions().mode() & 0o111 == 0 {
+        report.fail(
+            format!("Hook {} is not executable, so git skips it", hook.display()),
+            format!("chmod +x {}", hook.display()),
+        );
+        return;
+    }
+    if hook_runs_aittributor(&hook) {
+        report.ok(format!("Hook {} runs aittributor", hook.display()));
+    } else {
+        report.warn(
+            format!("Hook {} doesn't mention aittributor", hook.display()),
+            "if a hook manager runs it, make sure it passes the hook's arguments on to aittributor (see README); otherwise add `aittributor \"$@\"` to it",
+        );
+    }
+}
+
+fn check_interpret_trailers(report: &mut Report) {
+    let result = (|| -> std::io::Result<String> {
+        let mut child = std::process::Command::new("git")
+            .args(["interpret-trailers", "--trailer", "Ai-assisted: true"])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()?;
+        child.stdin.take().unwrap().write_all(b"Subject\n\nBody\n")?;
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(std::io::Error::other(
+                String::from_utf8_lossy(&output.stderr).to_string(),
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    })();
+    match result {
+        Ok(message) if message.contains("\nAi-assisted: true") => report.ok("git interpret-trailers works"),
+        Ok(message) => report.fail(
+            format!("git interpret-trailers didn't add the trailer: {:?}", message),
+            "check trailer.* settings in `git config --list`",
+        ),
+        Err(e) => report.fail(
+            format!("git interpret-trailers failed: {}", e.to_string().trim()),
+            "install git 2.15 or later and make sure it is on PATH",
+        ),
+    }
+}
+
+/// The `hidepid` mount option of /proc, from /proc/mounts.
+fn proc_hidepid(mounts: &str) -> Option<&str> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            (fields.len() >= 4 && fields[1] == "/proc" && fields[2] == "proc").then(|| fields[3])
+        })
+        .flat_map(|options| options.split(','))
+        .find_map(|option| option.strip_prefix("hidepid="))
+}
+
+fn check_process_visibility(report: &mut Report, table: &ProcessTable) {
+    if !cfg!(target_os = "linux") {
+        report.ok(format!("{} processes visible via {}", table.len(), table.backend));
+        return;
+    }
+    let uid = user::current_uid();
+    let others = table.iter().filter(|p| p.uid.is_some_and(|u| u != uid)).count();
+    match fs::read_to_string("/proc/mounts").ok().as_deref().and_then(proc_hidepid) {
+        Some("0" | "off") | None => report.ok(format!(
+            "{} processes visible via {} ({} owned by other users)",
+            table.len(),
+            table.backend,
+            others
+        )),
+        Some(mode) => report.warn(
+            format!(
+                "/proc is mounted with hidepid={}: other users' processes are hidden ({} visible)",
+                mode, others
+            ),
+            "agents running as your own user are still found; agents run by other users (e.g. in sandboxes) need a gid= exemption on the /proc mount",
+        ),
+    }
+}
+
+fn check_breadcrumb_permissions(report: &mut Report) {
+    let uid = user::current_uid();
+    let mut problems = 0;
+    for agent in Agent::all() {
+        for source in breadcrumbs::sources(agent) {
+            if !source.path.is_dir() {
+                continue;
+            }
+            if fs::read_dir(&source.path).is_err() {
+                problems += 1;
+                report.fail(
+                    format!("{} breadcrumbs at {} can't be read", agent.email, source.path.display()),
+                    format!("chmod u+rx {}", source.path.display()),
+                );
+            } else if user::owner_of(&source.path).is_some_and(|owner| owner != uid) {
+                problems += 1;
+                report.warn(
+                    format!(
+                        "{} breadcrumbs at {} belong to another user and are ignored",
+                        agent.email,
+                        source.path.display()
+                    ),
+                    "set include_other_users = true in the config if that is intended",
+                );
+            }
+        }
+    }
+    if problems == 0 {
+        report.ok("Breadcrumb dirs are readable");
+    }
+
+    if let Some(cache) = SessionCache::default_path()
+        && let Some(dir) = cache.parent()
+        && dir.exists()
+        && fs::metadata(dir).is_ok_and(|m| m.permissions().readonly())
+    {
+        report.warn(
+            format!("Session cache dir {} is read-only", dir.display()),
+            format!("chmod u+w {}", dir.display()),
+        );
+    }
+}
+
+fn check_timings(report: &mut Report, config: &Config) -> ProcessTable {
+    let roots = crate::repo_roots(config).unwrap_or_default();
+    let owner = (!config.include_other_users).then(user::current_uid);
+
+    let started = Instant::now();
+    let table = ProcessTable::load();
+    let load = started.elapsed();
+
+    let started = Instant::now();
+    crate::walk_ancestry(&table, std::process::id(), false);
+    let ancestry = started.elapsed();
+
+    let started = Instant::now();
+    let matcher = PathMatcher::new(&roots, &config.path_map).with_mount_info();
+    crate::walk_ancestry_and_descendants(&table, std::process::id(), &matcher, owner, false);
+    let descendants = started.elapsed();
+
+    let started = Instant::now();
+    for root in &roots {
+        breadcrumbs::detect_agents_from_breadcrumbs(root, config, false);
+    }
+    let breadcrumbs = started.elapsed();
+
+    // What the hook runs: rules, the ledger, the watcher timeline, and process and breadcrumb
+    // detection in parallel. The parts above are only a breakdown of it.
+    let started = Instant::now();
+    crate::detect_for_commit(config.clone(), &mut audit::Record::default(), false);
+    let total = started.elapsed();
+
+    println!("  process table ({}): {:?}", table.backend, load);
+    println!("  ancestry walk:        {:?}", ancestry);
+    println!("  sibling trees:        {:?}", descendants);
+    println!("  breadcrumbs:          {:?}", breadcrumbs);
+    println!("  whole detection:      {:?}", total);
+
+    if total >= HOOK_BUDGET {
+        report.fail(
+            format!(
+                "Detection takes {:?}, over the {:?} budget, so the hook gives up",
+                total, HOOK_BUDGET
+            ),
+            "run `aittributor --debug` to see what is slow; large breadcrumb dirs can be pruned",
+        );
+    } else if total >= HOOK_BUDGET / 2 {
+        report.warn(
+            format!("Detection takes {:?}, close to the {:?} budget", total, HOOK_BUDGET),
+            "run `aittributor --debug` to see what is slow",
+        );
+    } else {
+        report.ok(format!("Detection takes {:?} of the {:?} budget", total, HOOK_BUDGET));
+    }
+    table
+}
+
+/// Run every check, returning an error when any of them failed.
+pub fn run(config: &Config) -> Result<(), String> {
+    let mut report = Report::default();
+    check_config(&mut report);
+    check_hook(&mut report);
+    check_interpret_trailers(&mut report);
+    println!("Detection timings:");
+    let table = check_timings(&mut report, config);
+    check_process_visibility(&mut report, &table);
+    check_breadcrumb_permissions(&mut report);
+
+    match report.failures {
+        0 => Ok(()),
+        n => Err(format!("{} check(s) failed", n)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proc_hidepid() {
+        let mounts =
+            "sysfs /sys sysfs rw,nosuid 0 0\nproc /proc proc rw,nosuid,nodev,noexec,relatime,hidepid=invisible 0 0\n";
+        assert_eq!(proc_hidepid(mounts), Some("invisible"));
+        assert_eq!(proc_hidepid("proc /proc proc rw,relatime 0 0\n"), None);
+        assert_eq!(proc_hidepid("proc /mnt/proc proc rw,hidepid=2 0 0\n"), None);
+    }
+
+    #[test]
+    fn test_hook_runs_aittributor() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let hook = dir.path().join("prepare-commit-msg");
+        fs::write(&hook, "#!/bin/sh\nexec aittributor \"$@\"\n").unwrap();
+        assert!(hook_runs_aittributor(&hook));
+        fs::write(&hook, "#!/bin/sh\nexec other-tool \"$@\"\n").unwrap();
+        assert!(!hook_runs_aittributor(&hook));
+    }
+}
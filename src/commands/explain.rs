@@ -0,0 +1,77 @@
+use crate::git::git_stdout;
+use crate::notes;
+
+/// A duration in seconds as the largest whole unit, e.g. "3h".
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+/// Show which detection phase found each agent credited in `commit`, and through what.
+pub fn run(commit: &str) -> Result<(), String> {
+    let spec = format!("{}^{{commit}}", commit);
+    let sha = git_stdout(&["rev-parse", "--verify", "--quiet", &spec])
+        .ok_or_else(|| format!("unknown commit: {}", commit))?;
+    let summary = git_stdout(&["log", "-1", "--format=%h %s", &sha]).unwrap_or_else(|| sha.clone());
+    let record = notes::load(&sha).ok_or_else(|| {
+        format!(
+            "no evidence stored for {} (it is stored by `aittributor post-commit`, see README)",
+            commit
+        )
+    })?;
+
+    println!("{}", summary);
+    if record.candidates.is_empty() {
+        println!("  no agents were found");
+    }
+    let mut agents: Vec<&str> = Vec::new();
+    for candidate in &record.candidates {
+        if !agents.contains(&candidate.agent.as_str()) {
+            agents.push(&candidate.agent);
+        }
+    }
+    for agent in agents {
+        let credited = if record.trailers.iter().any(|t| t == agent) {
+            "credited"
+        } else {
+            "not credited"
+        };
+        println!("  {} ({})", agent, credited);
+        for candidate in record.candidates.iter().filter(|c| c.agent == agent) {
+            let mut found = format!("    found by {}", candidate.method.as_str());
+            if let Some(pid) = candidate.pid {
+                found.push_str(&format!(", PID {}", pid));
+            }
+            if let Some(breadcrumb) = &candidate.breadcrumb {
+                found.push_str(&format!(", {}", breadcrumb.display()));
+            }
+            match candidate.seen {
+                Some(seen) => found.push_str(&format!(
+                    ", last active {} before the commit",
+                    format_age(record.timestamp.saturating_sub(seen))
+                )),
+                None if candidate.pid.is_some() => found.push_str(", running at commit time"),
+                None => {}
+            }
+            println!("{}", found);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(5), "5s");
+        assert_eq!(format_age(150), "2m");
+        assert_eq!(format_age(7200), "2h");
+        assert_eq!(format_age(3 * 86400 + 5), "3d");
+    }
+}
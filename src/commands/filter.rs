@@ -0,0 +1,57 @@
+use std::io::{Read, Write};
+
+use crate::config::Config;
+use crate::{audit, git, ledger, notes};
+
+/// Credit the detected agents in a commit message read from stdin, within the hook's time
+/// budget. The message is always written back, unchanged when attribution is off, timed out
+/// or failed, so a client piping through the filter never loses it.
+pub fn run(config: Config, debug: bool) -> Result<(), String> {
+    let mut message = String::new();
+    std::io::stdin()
+        .read_to_string(&mut message)
+        .map_err(|e| format!("reading the commit message: {}", e))?;
+
+    let timestamp = ledger::now_secs();
+    let input = message.clone();
+    let message = crate::within_budget(crate::HOOK_BUDGET, move |clock| {
+        add_trailers(config, input, timestamp, debug, clock)
+    })
+    .unwrap_or_else(|| {
+        crate::timed_out(timestamp, String::new(), true, debug);
+        message
+    });
+
+    std::io::stdout()
+        .write_all(message.as_bytes())
+        .map_err(|e| format!("writing the commit message: {}", e))
+}
+
+/// `message` with trailers for the agents credited, logging what was done.
+fn add_trailers(
+    config: Config,
+    mut message: String,
+    timestamp: u64,
+    debug: bool,
+    clock: &crate::Clock<String>,
+) -> String {
+    let mut record = audit::Record {
+        timestamp,
+        repo: crate::current_repo_path().unwrap_or_default(),
+        ..audit::Record::default()
+    };
+    if let Some((config, detection)) = crate::detect_for_commit(config, &mut record, debug) {
+        for agent in crate::credited_agents(&config, &detection, clock) {
+            match git::add_trailers(&message, agent, debug) {
+                Ok(updated) => {
+                    message = updated;
+                    record.trailers.push(agent.email.to_string());
+                }
+                Err(e) => eprintln!("aittributor: failed to append trailers: {}", e),
+            }
+        }
+        notes::save_pending(&record, debug);
+    }
+    audit::log(&record, debug);
+    message
+}
@@ -0,0 +1,222 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use serde_json::{Value, json};
+
+use crate::diff;
+use crate::user;
+
+/// Agents with a hook system that can run `aittributor mark` on their edits.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Integration {
+    /// Claude Code: a PostToolUse hook in settings.json
+    Claude,
+    /// Codex: the notify command in config.toml, run after each turn
+    Codex,
+    /// Gemini CLI: an AfterTool hook in settings.json
+    Gemini,
+    /// Copilot CLI: a postToolUse hook in the repository's .github/hooks/
+    Copilot,
+}
+
+/// Tells whether an existing hook is ours, whatever the rest of its command line says.
+const MARKER: &str = "aittributor mark";
+
+fn home_or_env(var: &str, default: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| user::home_dir().map(|h| h.join(default)))
+}
+
+impl Integration {
+    fn path(self) -> Option<PathBuf> {
+        match self {
+            Integration::Claude => home_or_env("CLAUDE_CONFIG_DIR", ".claude").map(|d| d.join("settings.json")),
+            Integration::Codex => home_or_env("CODEX_HOME", ".codex").map(|d| d.join("config.toml")),
+            Integration::Gemini => user::home_dir().map(|h| h.join(".gemini").join("settings.json")),
+            // Copilot CLI only reads hooks from the repository.
+            Integration::Copilot => {
+                crate::current_repo_path().map(|repo| repo.join(".github").join("hooks").join("aittributor.json"))
+            }
+        }
+    }
+
+    /// The config with the hook added, or unchanged if it is already there.
+    fn merge(self, existing: &str) -> Result<String, String> {
+        let command = |agent: &str| format!("{} --agent {} --from-hook", MARKER, agent);
+        match self {
+            Integration::Claude => merge_json_hook(
+                existing,
+                &["hooks", "PostToolUse"],
+                json!({
+                    "matcher": "Edit|Write|MultiEdit|NotebookEdit",
+                    "hooks": [{"type": "command", "command": command("claude")}],
+                }),
+            ),
+            Integration::Gemini => merge_json_hook(
+                existing,
+                &["hooks", "AfterTool"],
+                json!({
+                    "matcher": "write_file|replace",
+                    "hooks": [{"type": "command", "command": command("gemini")}],
+                }),
+            ),
+            Integration::Copilot => {
+                let existing = if existing.trim().is_empty() {
+                    r#"{"version": 1}"#
+                } else {
+                    existing
+                };
+                merge_json_hook(
+                    existing,
+                    &["hooks", "postToolUse"],
+                    json!({"type": "command", "bash": command("copilot"), "timeoutSec": 10}),
+                )
+            }
+            Integration::Codex => merge_codex_notify(existing),
+        }
+    }
+}
+
+fn mentions_marker(value: &Value) -> bool {
+    match value {
+        Value::String(s) => s.contains(MARKER),
+        Value::Array(items) => items.iter().any(mentions_marker),
+        Value::Object(map) => map.values().any(mentions_marker),
+        _ => false,
+    }
+}
+
+/// Append `entry` to the array at `keys` in a JSON settings file, creating what's missing.
+/// Other settings, and their order, are kept.
+fn merge_json_hook(existing: &str, keys: &[&str], entry: Value) -> Result<String, String> {
+    let mut settings: Value = if existing.trim().is_empty() {
+        json!({})
+    } else {
+        serde_json::from_str(existing).map_err(|e| e.to_string())?
+    };
+    let mut node = &mut settings;
+    for (i, key) in keys.iter().enumerate() {
+        node = node
+            .as_object_mut()
+            .ok_or_else(|| format!("expected an object around \"{}\"", key))?
+            .entry(*key)
+            .or_insert_with(|| if i == keys.len() - 1 { json!([]) } else { json!({}) });
+    }
+    let hooks = node
+        .as_array_mut()
+        .ok_or_else(|| format!("expected \"{}\" to be an array", keys.join(".")))?;
+    if mentions_marker(&Value::Array(hooks.clone())) {
+        return Ok(existing.to_string());
+    }
+    hooks.push(entry);
+    serde_json::to_string_pretty(&settings)
+        .map(|s| s + "\n")
+        .map_err(|e| e.to_string())
+}
+
+/// Codex runs a single notify command, passing it a JSON payload as its last argument, after
+/// each turn. Top-level keys must come before any table, so the line goes first; editing the
+/// text keeps the user's comments.
+fn merge_codex_notify(existing: &str) -> Result<String, String> {
+    let config: toml::Table = toml::from_str(existing).map_err(|e| e.to_string())?;
+    match config.get("notify") {
+        Some(notify) if notify.to_string().contains("aittributor") => Ok(existing.to_string()),
+        Some(notify) => Err(format!(
+            "Codex already runs notify = {}, and it takes only one command; call `{} --agent codex --payload \"$1\"` from it",
+            notify, MARKER
+        )),
+        None => Ok(format!(
+            "notify = [\"aittributor\", \"mark\", \"--agent\", \"codex\", \"--payload\"]\n{}",
+            existing
+        )),
+    }
+}
+
+pub fn run(integration: Integration, dry_run: bool) -> Result<(), String> {
+    let path = integration
+        .path()
+        .ok_or("cannot determine where the agent's config lives")?;
+    let existing = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    let merged = integration
+        .merge(&existing)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    if merged == existing {
+        println!("{} already reports to aittributor", path.display());
+        return Ok(());
+    }
+    if dry_run {
+        println!("--- {}\n+++ {}", path.display(), path.display());
+        print!("{}", diff::diff(&existing, &merged));
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+    fs::write(&path, merged).map_err(|e| format!("{}: {}", path.display(), e))?;
+    println!("Updated {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_claude_keeps_settings_and_is_idempotent() {
+        let existing = r#"{
+  "model": "opus",
+  "hooks": {
+    "Stop": [{"hooks": [{"type": "command", "command": "say done"}]}]
+  },
+  "env": {"FOO": "1"}
+}
+"#;
+        let merged = Integration::Claude.merge(existing).unwrap();
+        let settings: Value = serde_json::from_str(&merged).unwrap();
+        let keys: Vec<&String> = settings.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["model", "hooks", "env"]);
+        assert_eq!(settings["hooks"]["Stop"][0]["hooks"][0]["command"], "say done");
+        assert_eq!(
+            settings["hooks"]["PostToolUse"][0]["hooks"][0]["command"],
+            "aittributor mark --agent claude --from-hook"
+        );
+        assert_eq!(Integration::Claude.merge(&merged).unwrap(), merged);
+    }
+
+    #[test]
+    fn test_merge_copilot_into_new_file() {
+        let merged = Integration::Copilot.merge("").unwrap();
+        let hooks: Value = serde_json::from_str(&merged).unwrap();
+        assert_eq!(hooks["version"], 1);
+        assert_eq!(
+            hooks["hooks"]["postToolUse"][0]["bash"],
+            "aittributor mark --agent copilot --from-hook"
+        );
+    }
+
+    #[test]
+    fn test_merge_rejects_unexpected_shapes() {
+        assert!(Integration::Gemini.merge(r#"{"hooks": []}"#).is_err());
+        assert!(Integration::Gemini.merge("not json").is_err());
+    }
+
+    #[test]
+    fn test_merge_codex_notify() {
+        let existing = "# my config\nmodel = \"o3\"\n\n[profiles.fast]\nmodel = \"o4-mini\"\n";
+        let merged = Integration::Codex.merge(existing).unwrap();
+        assert!(merged.starts_with("notify = [\"aittributor\", \"mark\", \"--agent\", \"codex\", \"--payload\"]\n"));
+        assert!(merged.ends_with(existing));
+        let config: toml::Table = toml::from_str(&merged).unwrap();
+        assert_eq!(config["notify"].as_array().unwrap().len(), 5);
+        assert_eq!(Integration::Codex.merge(&merged).unwrap(), merged);
+
+        assert!(Integration::Codex.merge("notify = [\"notify-send\"]\n").is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use crate::audit::{self, Record};
+
+/// Unix seconds as local "YYYY-MM-DD HH:MM:SS".
+fn local_time(secs: u64) -> String {
+    let secs = secs as libc::time_t;
+    // SAFETY: an all-zero tm is a valid value for localtime_r to fill in.
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe { libc::localtime_r(&secs, &mut tm) };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+fn mentions_agent(record: &Record, name: &str) -> bool {
+    let name = name.to_lowercase();
+    record
+        .candidates
+        .iter()
+        .map(|c| &c.agent)
+        .chain(&record.trailers)
+        .any(|email| email.to_lowercase().contains(&name))
+}
+
+fn print_record(record: &Record) {
+    let source = match record.commit_source.as_str() {
+        "" => String::new(),
+        source => format!("  ({})", source),
+    };
+    println!("{}  {}{}", local_time(record.timestamp), record.repo.display(), source);
+    if let Some(rule) = record.rule {
+        println!(
+            "  rule {}{}",
+            rule,
+            if record.disabled { ": attribution disabled" } else { "" }
+        );
+    }
+    if record.timed_out {
+        println!("  timed out, nothing added");
+    }
+    for candidate in &record.candidates {
+        let mark = if record.trailers.contains(&candidate.agent) {
+            '✓'
+        } else {
+            '✗'
+        };
+        let mut line = format!("  {} {}  via {}", mark, candidate.agent, candidate.method.as_str());
+        if let Some(pid) = candidate.pid {
+            line.push_str(&format!(", PID {}", pid));
+        }
+        if let Some(breadcrumb) = &candidate.breadcrumb {
+            line.push_str(&format!(", {}", breadcrumb.display()));
+        }
+        println!("{}", line);
+    }
+    if !record.disabled && !record.timed_out && record.trailers.is_empty() {
+        println!("  no trailers added");
+    }
+}
+
+/// Print the latest `limit` hook runs in the current repository (or all of them), oldest first.
+pub fn run(all: bool, agent: Option<&str>, limit: usize, json: bool) -> Result<(), String> {
+    let path = audit::path().ok_or("cannot determine the state directory")?;
+    let repo_path = crate::current_repo_path();
+    let records: Vec<Record> = audit::read(&path)
+        .into_iter()
+        .filter(|r| all || repo_path.as_ref() == Some(&r.repo))
+        .filter(|r| agent.is_none_or(|name| mentions_agent(r, name)))
+        .collect();
+    if records.is_empty() {
+        println!(
+            "No hook runs logged{} in {}",
+            if all { "" } else { " for this repository" },
+            path.display()
+        );
+        return Ok(());
+    }
+    for record in &records[records.len().saturating_sub(limit)..] {
+        if json {
+            println!("{}", serde_json::to_string(record).map_err(|e| e.to_string())?);
+        } else {
+            print_record(record);
+        }
+    }
+    Ok(())
+}
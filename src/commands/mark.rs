@@ -0,0 +1,234 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::agent::Agent;
+use crate::config::Config;
+use crate::ledger::{self, Entry};
+use crate::paths::PathMatcher;
+
+/// Keys agent hook payloads name edited files under: Claude and Gemini tool inputs, Copilot's
+/// `toolArgs`, and multi-file edits nesting them in arrays.
+const FILE_KEYS: &[&str] = &["file_path", "filePath", "absolute_path", "notebook_path", "path"];
+const SESSION_KEYS: &[&str] = &["session_id", "sessionId", "thread-id", "conversation_id"];
+const TOOL_KEYS: &[&str] = &["tool_name", "toolName"];
+
+/// Tools that write files, by the names Claude, Gemini and Copilot give them (compared
+/// case-insensitively). Copilot's hook runs after every tool, including `bash` and `view`.
+const EDIT_TOOLS: &[&str] = &[
+    "edit",
+    "multiedit",
+    "notebookedit",
+    "write",
+    "create",
+    "write_file",
+    "replace",
+    "str_replace",
+    "apply_patch",
+];
+
+fn collect_files(value: &serde_json::Value, files: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                match value {
+                    serde_json::Value::String(s) if FILE_KEYS.contains(&key.as_str()) => files.push(s.clone()),
+                    // Copilot passes tool arguments as a JSON-encoded string.
+                    serde_json::Value::String(s) if s.starts_with('{') => {
+                        if let Ok(nested) = serde_json::from_str(s) {
+                            collect_files(&nested, files);
+                        }
+                    }
+                    _ => collect_files(value, files),
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter().for_each(|item| collect_files(item, files)),
+        _ => {}
+    }
+}
+
+/// What a hook payload tells about the edit: files, session id, working directory and the
+/// tool that ran, for hooks run after each tool call.
+struct Payload {
+    files: Vec<String>,
+    session: Option<String>,
+    cwd: Option<PathBuf>,
+    tool: Option<String>,
+}
+
+impl Payload {
+    /// Whether the payload reports an edit. Payloads naming no tool (e.g. Codex's turn
+    /// notifications) are taken to.
+    fn is_edit(&self) -> bool {
+        self.tool
+            .as_deref()
+            .is_none_or(|tool| EDIT_TOOLS.contains(&tool.to_lowercase().as_str()))
+    }
+}
+
+fn parse_payload(json: &str) -> Result<Payload, String> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|e| format!("invalid hook payload: {}", e))?;
+    let mut files = Vec::new();
+    collect_files(&value, &mut files);
+    let field = |keys: &[&str]| keys.iter().find_map(|k| value.get(*k)?.as_str().map(str::to_string));
+    Ok(Payload {
+        files,
+        session: field(SESSION_KEYS),
+        cwd: field(&["cwd"]).map(PathBuf::from),
+        tool: field(TOOL_KEYS),
+    })
+}
+
+/// Paths relative to the repository root, dropping any outside it. Agents in a container or
+/// behind a symlink may name the repository differently, so paths go through the matcher.
+fn repo_relative(files: &[PathBuf], cwd: &Path, matcher: &PathMatcher) -> Vec<String> {
+    files
+        .iter()
+        .filter_map(|file| {
+            let relative = matcher.relative(&cwd.join(file))?;
+            let relative = relative.to_str()?;
+            (!relative.is_empty()).then(|| relative.to_string())
+        })
+        .collect()
+}
+
+pub fn run(
+    config: &Config,
+    agent_name: &str,
+    session: Option<String>,
+    from_hook: bool,
+    payload: Option<String>,
+    files: Vec<PathBuf>,
+) -> Result<(), String> {
+    let agent = Agent::find_by_name_or_email(agent_name).ok_or_else(|| {
+        format!(
+            "unknown agent: {} (define it under [[agents]] in the config)",
+            agent_name
+        )
+    })?;
+
+    let payload = match (from_hook, payload) {
+        (true, _) => {
+            let mut json = String::new();
+            std::io::stdin()
+                .read_to_string(&mut json)
+                .map_err(|e| format!("reading hook payload: {}", e))?;
+            Some(parse_payload(&json)?)
+        }
+        (false, Some(json)) => Some(parse_payload(&json)?),
+        (false, None) => None,
+    };
+    if payload.as_ref().is_some_and(|p| !p.is_edit()) {
+        return Ok(());
+    }
+    if let Some(cwd) = payload.as_ref().and_then(|p| p.cwd.as_ref()) {
+        std::env::set_current_dir(cwd).map_err(|e| format!("{}: {}", cwd.display(), e))?;
+    }
+
+    let repo_path = crate::current_repo_path().ok_or("cannot determine the current directory")?;
+    let cwd = std::env::current_dir().map_err(|e| e.to_string())?;
+    let mut paths = files;
+    if let Some(payload) = &payload {
+        paths.extend(payload.files.iter().map(PathBuf::from));
+    }
+    let mut files = if paths.is_empty() {
+        // Hooks that don't say what changed (e.g. Codex's turn notifications) cover what
+        // changed since the agent's previous turn. Edits all outside the repository cover nothing.
+        ledger::changed_since_last_turn(agent.email, &repo_path)
+    } else {
+        let matcher = PathMatcher::new(std::slice::from_ref(&repo_path), &config.path_map);
+        repo_relative(&paths, &cwd, &matcher)
+    };
+    if files.is_empty() {
+        return Ok(());
+    }
+    files.sort();
+    files.dedup();
+
+    let entry = Entry {
+        agent: agent.email.to_string(),
+        files,
+        timestamp: ledger::now_secs(),
+        session: session.or_else(|| payload.and_then(|p| p.session)),
+    };
+    let path = ledger::path().ok_or("not in a git repository")?;
+    ledger::append(&path, &entry).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_claude_post_tool_use() {
+        let payload = parse_payload(
+            r#"{"session_id":"abc","cwd":"/src/repo","hook_event_name":"PostToolUse","tool_name":"Edit",
+                "tool_input":{"file_path":"/src/repo/src/main.rs","old_string":"a","new_string":"b"}}"#,
+        )
+        .unwrap();
+        assert_eq!(payload.files, ["/src/repo/src/main.rs"]);
+        assert_eq!(payload.session.as_deref(), Some("abc"));
+        assert_eq!(payload.cwd, Some(PathBuf::from("/src/repo")));
+    }
+
+    #[test]
+    fn test_parse_copilot_tool_args_string() {
+        let payload = parse_payload(
+            r#"{"timestamp":1,"cwd":"/src/repo","toolName":"edit","toolArgs":"{\"path\":\"src/lib.rs\"}"}"#,
+        )
+        .unwrap();
+        assert_eq!(payload.files, ["src/lib.rs"]);
+        assert!(payload.is_edit());
+    }
+
+    #[test]
+    fn test_copilot_non_edit_tools_are_skipped() {
+        let shell = parse_payload(
+            r#"{"timestamp":1,"cwd":"/src/repo","toolName":"bash","toolArgs":"{\"command\":\"cargo test\"}"}"#,
+        )
+        .unwrap();
+        assert!(shell.files.is_empty());
+        assert!(!shell.is_edit());
+
+        let view =
+            parse_payload(r#"{"timestamp":1,"cwd":"/src/repo","toolName":"view","toolArgs":"{\"path\":\"a.rs\"}"}"#)
+                .unwrap();
+        assert_eq!(view.files, ["a.rs"]);
+        assert!(!view.is_edit());
+    }
+
+    #[test]
+    fn test_parse_codex_notification_has_no_files() {
+        let payload = parse_payload(
+            r#"{"type":"agent-turn-complete","thread-id":"t1","cwd":"/src/repo","input-messages":["fix it"]}"#,
+        )
+        .unwrap();
+        assert!(payload.files.is_empty());
+        assert_eq!(payload.session.as_deref(), Some("t1"));
+        assert!(payload.is_edit());
+    }
+
+    #[test]
+    fn test_repo_relative() {
+        let files = [
+            PathBuf::from("/src/repo/a.rs"),
+            PathBuf::from("b/c.rs"),
+            PathBuf::from("/elsewhere/d.rs"),
+        ];
+        let maps = [crate::config::PathMap {
+            from: "/workspaces".to_string(),
+            to: "/src".to_string(),
+        }];
+        let matcher = PathMatcher::new(&[PathBuf::from("/src/repo")], &maps);
+        assert_eq!(
+            repo_relative(&files, Path::new("/src/repo/pkg"), &matcher),
+            ["a.rs", "pkg/b/c.rs"]
+        );
+        // The same repository, as seen from a devcontainer.
+        assert_eq!(
+            repo_relative(&files, Path::new("/workspaces/repo/pkg"), &matcher),
+            ["a.rs", "pkg/b/c.rs"]
+        );
+        assert!(repo_relative(&files[2..], Path::new("/src/repo"), &matcher).is_empty());
+    }
+}
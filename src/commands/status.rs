@@ -0,0 +1,44 @@
+use crate::config::{Config, Rule};
+use crate::rules::{self, RepoInfo};
+
+fn describe_rule(rule: &Rule) -> String {
+    let conditions: Vec<String> = [("remote", &rule.remote), ("path", &rule.path), ("branch", &rule.branch)]
+        .into_iter()
+        .filter_map(|(key, pattern)| pattern.as_ref().map(|p| format!("{} = {:?}", key, p)))
+        .collect();
+    if conditions.is_empty() {
+        "any repository".to_string()
+    } else {
+        conditions.join(", ")
+    }
+}
+
+/// Show how the config applies to the current repository.
+pub fn run(config: &Config) -> Result<(), String> {
+    let repo = RepoInfo::current().ok_or("cannot determine the current directory")?;
+    println!("Repository: {}", repo.path.display());
+    if repo.remotes.is_empty() {
+        println!("Remotes:    none");
+    }
+    for remote in &repo.remotes {
+        println!("Remote:     {}", remote);
+    }
+    println!("Branch:     {}", repo.branch.as_deref().unwrap_or("(detached)"));
+    if let Some(path) = Config::path() {
+        println!("Config:     {}", path.display());
+    }
+
+    let resolution = rules::resolve(config, &repo);
+    match resolution.rule {
+        Some(i) => println!("\nRule {} applies: {}", i + 1, describe_rule(&config.rules[i])),
+        None if config.rules.is_empty() => println!("\nNo rules configured"),
+        None => println!("\nNo rule matches, using the top-level settings"),
+    }
+    println!(
+        "  attribution:         {}",
+        if resolution.enabled { "enabled" } else { "disabled" }
+    );
+    println!("  include_other_users: {}", resolution.config.include_other_users);
+    println!("  interactive:         {}", resolution.config.interactive);
+    Ok(())
+}
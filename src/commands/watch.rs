@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use crate::activity::{self, Timeline, Watcher};
+use crate::config::Config;
+use crate::ledger::now_secs;
+use crate::process::ProcessTable;
+use crate::user;
+
+/// Sample the process table every `interval` seconds until killed, appending agent activity
+/// to the timeline. Only one watcher runs per user, which holds the timeline's lock.
+pub fn run(config: &Config, interval: u64) -> Result<(), String> {
+    let path = activity::path().ok_or("cannot determine the state directory")?;
+    let timeline = Timeline::lock(&path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?
+        .ok_or_else(|| format!("another watcher is recording to {}", path.display()))?;
+
+    let owner = (!config.include_other_users).then(user::current_uid);
+    let mut watcher = Watcher::default();
+    eprintln!("aittributor: recording agent activity to {}", path.display());
+    loop {
+        let now = now_secs();
+        let spans = watcher.sample(&ProcessTable::load(), owner, now);
+        if let Err(e) = timeline.append(&spans, now) {
+            eprintln!("aittributor: {}: {}", path.display(), e);
+        }
+        std::thread::sleep(Duration::from_secs(interval.max(1)));
+    }
+}
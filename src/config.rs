@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::user::xdg_dir;
+
+/// User configuration, read from `$AITTRIBUTOR_CONFIG` or `$XDG_CONFIG_HOME/aittributor/config.toml`
+/// (`~/.config/aittributor/config.toml` when XDG_CONFIG_HOME is unset).
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Attribute agent processes and breadcrumbs owned by other users. Off by default so that
+    /// on shared machines a colleague's agent working in a clone at the same path isn't credited.
+    pub include_other_users: bool,
+    /// Ask on the terminal which agents to credit, unless an agent is making the commit.
+    pub interactive: bool,
+    /// Seconds to wait for an answer before keeping the detected agents.
+    pub interactive_timeout_secs: Option<u64>,
+    /// Credit agents working in the main worktree for commits in its linked worktrees.
+    pub match_main_worktree: bool,
+    /// Credit agents working in the superproject for commits in its submodules.
+    pub match_superproject: bool,
+    /// Agents aittributor doesn't know about, checked before the built-in ones.
+    pub agents: Vec<AgentConfig>,
+    pub rules: Vec<Rule>,
+    pub path_map: Vec<PathMap>,
+}
+
+/// A `[[path_map]]` table for agents that see the repository at another path than git does,
+/// as in devcontainers or VMs with shared folders.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathMap {
+    /// Path prefix as agents report it, e.g. `/workspaces`.
+    pub from: String,
+    /// The same place as git sees it, e.g. `~/src`.
+    pub to: String,
+}
+
+/// A `[[rules]]` table scoping attribution to some repositories. The first rule whose
+/// conditions all match the repository applies; a rule without conditions matches any.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rule {
+    /// Glob matched against each remote URL, as written or as `host/owner/repo`
+    /// (e.g. `github.com/acme/*`).
+    pub remote: Option<String>,
+    /// Glob matched against the repository root; a leading `~/` is the home directory.
+    pub path: Option<String>,
+    /// Glob matched against the current branch. Never matches a detached HEAD.
+    pub branch: Option<String>,
+    /// Whether to attribute at all. Defaults to true.
+    pub enabled: Option<bool>,
+    /// Override the top-level settings of the same names.
+    pub include_other_users: Option<bool>,
+    pub interactive: Option<bool>,
+}
+
+/// An agent defined in the config file as a `[[agents]]` table.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentConfig {
+    /// A built-in agent to detect as usual but credit with `email`, for those shipped without
+    /// one, e.g. `"avante"`. The other fields add to its detection.
+    pub name: Option<String>,
+    /// Co-author trailer value, e.g. `"Acme Agent <agent@acme.dev>"`.
+    pub email: String,
+    #[serde(default)]
+    pub process_names: Vec<String>,
+    #[serde(default)]
+    pub exact_process_match: bool,
+    /// Environment variables that must all be set to these values.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Session dir relative to the home directory, walked for recent files with a `"cwd"` field.
+    pub breadcrumb_dir: Option<String>,
+    pub breadcrumb_ext: Option<String>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("AITTRIBUTOR_CONFIG").filter(|p| !p.is_empty()) {
+            return Some(PathBuf::from(path));
+        }
+        Some(
+            xdg_dir("XDG_CONFIG_HOME", ".config")?
+                .join("aittributor")
+                .join("config.toml"),
+        )
+    }
+
+    /// Load the config file. A missing file yields the defaults.
+    pub fn load_from(path: &PathBuf) -> Result<Config, String> {
+        match fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content).map_err(|e| e.to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Load the config, falling back to defaults on any error. A broken config must never
+    /// block a commit.
+    pub fn load() -> Config {
+        let Some(path) = Self::path() else {
+            return Config::default();
+        };
+        Self::load_from(&path).unwrap_or_else(|e| {
+            eprintln!("aittributor: ignoring invalid config {}: {}", path.display(), e);
+            Config::default()
+        })
+    }
+}
@@ -1,34 +1,143 @@
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
 
 use crate::agent::Agent;
+use crate::config::Config;
 
-pub fn find_git_root(start_path: &Path) -> Option<PathBuf> {
-    let mut current = start_path.to_path_buf();
+/// A repository as git resolves it: the working tree being committed to, and for linked
+/// worktrees and submodules, the checkouts they belong to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Repo {
+    pub work_tree: PathBuf,
+    /// For a linked worktree, its own dir under the main repository's `.git/worktrees/`.
+    pub git_dir: PathBuf,
+    /// The main worktree, when this is a linked worktree of a non-bare repository.
+    pub main_worktree: Option<PathBuf>,
+    /// The superproject's working tree, when this is a submodule.
+    pub superproject: Option<PathBuf>,
+}
+
+impl Repo {
+    /// The repository git is running in. Git exports `GIT_DIR` to hooks in linked worktrees and
+    /// submodules, running them from the top of the working tree.
+    pub fn current() -> Option<Repo> {
+        let cwd = std::env::current_dir().ok()?;
+        let env = |var| {
+            std::env::var_os(var)
+                .filter(|v| !v.is_empty())
+                .map(|v| normalize(&cwd.join(v)))
+        };
+        match env("GIT_DIR") {
+            // Like git, take the current directory as the top of the working tree unless told
+            // otherwise.
+            Some(git_dir) => Some(Repo::new(env("GIT_WORK_TREE").unwrap_or(cwd), git_dir)),
+            None => Repo::discover(&cwd),
+        }
+    }
+
+    /// The repository containing `path`, found by walking up to a `.git` dir, or a `.git` file
+    /// pointing at the git dir as in linked worktrees and submodules.
+    pub fn discover(path: &Path) -> Option<Repo> {
+        path.ancestors().find_map(|dir| {
+            let dot_git = dir.join(".git");
+            let git_dir = if dot_git.is_dir() {
+                dot_git
+            } else {
+                let content = fs::read_to_string(&dot_git).ok()?;
+                normalize(&dir.join(content.strip_prefix("gitdir:")?.trim()))
+            };
+            Some(Repo::new(dir.to_path_buf(), git_dir))
+        })
+    }
 
-    loop {
-        let git_dir = current.join(".git");
-        if git_dir.exists() {
-            return Some(current);
+    fn new(work_tree: PathBuf, git_dir: PathBuf) -> Repo {
+        // Linked worktrees name the main repository's git dir in `commondir`.
+        let main_worktree = fs::read_to_string(git_dir.join("commondir"))
+            .ok()
+            .map(|common| normalize(&git_dir.join(common.trim())))
+            .filter(|common| common.file_name().is_some_and(|name| name == ".git"))
+            .and_then(|common| Some(common.parent()?.to_path_buf()));
+        // A submodule is checked out inside its superproject, which lists it in `.gitmodules`
+        // and usually keeps its git dir under `.git/modules/`. A linked worktree checked out
+        // inside its main worktree has its git dir under `.git/worktrees/`, and isn't one.
+        let superproject = work_tree
+            .parent()
+            .and_then(Repo::discover)
+            .filter(|outer| {
+                git_dir.starts_with(outer.git_dir.join("modules")) || lists_submodule(&outer.work_tree, &work_tree)
+            })
+            .map(|outer| outer.work_tree);
+        Repo {
+            work_tree,
+            git_dir,
+            main_worktree,
+            superproject,
         }
+    }
 
-        match current.parent() {
-            Some(parent) => current = parent.to_path_buf(),
-            None => return None,
+    /// Directories an agent must be working in to be credited: the working tree, plus the main
+    /// worktree and superproject when the config opts in.
+    pub fn roots(&self, config: &Config) -> Vec<PathBuf> {
+        let mut roots = vec![self.work_tree.clone()];
+        if config.match_main_worktree {
+            roots.extend(self.main_worktree.clone());
+        }
+        if config.match_superproject {
+            roots.extend(self.superproject.clone());
         }
+        roots
     }
 }
 
+/// Whether the superproject at `outer` has a `path = ...` entry in `.gitmodules` for `inner`.
+fn lists_submodule(outer: &Path, inner: &Path) -> bool {
+    let (Ok(gitmodules), Ok(relative)) = (fs::read_to_string(outer.join(".gitmodules")), inner.strip_prefix(outer))
+    else {
+        return false;
+    };
+    gitmodules.lines().any(|line| {
+        line.trim()
+            .strip_prefix("path")
+            .and_then(|rest| rest.trim_start().strip_prefix('='))
+            .is_some_and(|path| Path::new(path.trim()) == relative)
+    })
+}
+
+/// Resolve `.` and `..` without touching the filesystem, as git does for `gitdir:` paths.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Write trailers crediting `agent` into the commit message file.
 pub fn append_trailers(commit_msg_file: &PathBuf, agent: &Agent, debug: bool) -> std::io::Result<()> {
     let content = fs::read_to_string(commit_msg_file)?;
+    let updated = add_trailers(&content, agent, debug)?;
+    if updated != content {
+        fs::write(commit_msg_file, updated)?;
+    }
+    Ok(())
+}
 
+/// `message` with trailers crediting `agent`, unless it already credits the agent's address.
+pub fn add_trailers(message: &str, agent: &Agent, debug: bool) -> std::io::Result<String> {
     let addr = Agent::extract_email_addr(agent.email);
-    if content.contains("Co-authored-by:") && content.contains(addr) {
+    if message.contains("Co-authored-by:") && message.contains(addr) {
         if debug {
             eprintln!("\n=== Git Command ===");
             eprintln!("Trailers already present, skipping git interpret-trailers");
         }
-        return Ok(());
+        return Ok(message.to_string());
     }
 
     let co_authored = format!("Co-authored-by: {}", agent.email);
@@ -36,23 +145,30 @@ pub fn append_trailers(commit_msg_file: &PathBuf, agent: &Agent, debug: bool) ->
     if debug {
         eprintln!("\n=== Git Command ===");
         eprintln!(
-            "git interpret-trailers --in-place --trailer \"{}\" --if-exists addIfDifferent --trailer \"Ai-assisted: true\" \"{}\"",
-            co_authored,
-            commit_msg_file.display()
+            "git interpret-trailers --trailer \"{}\" --if-exists addIfDifferent --trailer \"Ai-assisted: true\"",
+            co_authored
         );
     }
 
-    let output = std::process::Command::new("git")
+    let mut child = std::process::Command::new("git")
         .arg("interpret-trailers")
-        .arg("--in-place")
         .arg("--trailer")
         .arg(&co_authored)
         .arg("--if-exists")
         .arg("addIfDifferent")
         .arg("--trailer")
         .arg("Ai-assisted: true")
-        .arg(commit_msg_file)
-        .output()?;
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    // git reads the whole message before writing any of it, so this can't fill both pipes.
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(message.as_bytes())?;
+    let output = child.wait_with_output()?;
 
     if !output.status.success() {
         return Err(std::io::Error::other(format!(
@@ -61,5 +177,72 @@ pub fn append_trailers(commit_msg_file: &PathBuf, agent: &Agent, debug: bool) ->
         )));
     }
 
-    Ok(())
+    String::from_utf8(output.stdout).map_err(std::io::Error::other)
+}
+
+/// Run git in the current directory, returning its trimmed stdout when it succeeds.
+pub fn git_stdout(args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+}
+
+fn split_nul(output: &str) -> Vec<String> {
+    output
+        .split('\0')
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Paths staged for the next commit, relative to the repository root.
+pub fn staged_files() -> Vec<String> {
+    git_stdout(&["diff", "--cached", "--name-only", "-z"])
+        .map(|out| split_nul(&out))
+        .unwrap_or_default()
+}
+
+/// Paths with uncommitted changes in the working tree or index, including untracked files,
+/// relative to the repository root.
+pub fn changed_files() -> Vec<String> {
+    let Some(out) = git_stdout(&["status", "--porcelain", "-z", "--untracked-files=all"]) else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    let mut entries = out.split('\0').filter(|s| !s.is_empty());
+    while let Some(entry) = entries.next() {
+        let Some(path) = entry.get(3..) else {
+            continue;
+        };
+        files.push(path.to_string());
+        // Renames and copies are followed by their source path.
+        if entry.starts_with(['R', 'C']) {
+            entries.next();
+        }
+    }
+    files
+}
+
+/// The object `rev` names, or None if it doesn't resolve (like HEAD before the first commit).
+pub fn rev_parse(rev: &str) -> Option<String> {
+    git_stdout(&["rev-parse", "--verify", "--quiet", rev])
+}
+
+/// The tree the index would be committed as. Fails while the index has conflicts.
+pub fn index_tree() -> Option<String> {
+    git_stdout(&["write-tree"])
+}
+
+/// Committer time of HEAD in unix seconds, or None before the first commit.
+pub fn head_commit_time() -> Option<u64> {
+    git_stdout(&["log", "-1", "--format=%ct", "HEAD"])?.parse().ok()
+}
+
+/// A path inside the git dir, as `git rev-parse --git-path` resolves it (so linked worktrees
+/// each get their own), made absolute.
+pub fn git_path(path: &str) -> Option<PathBuf> {
+    let resolved = PathBuf::from(git_stdout(&["rev-parse", "--git-path", path])?);
+    Some(std::env::current_dir().ok()?.join(resolved))
 }
@@ -0,0 +1,290 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::Agent;
+use crate::audit::{Candidate, Method};
+use crate::git;
+use crate::lock::Lock;
+
+/// Once the ledger grows past this, entries older than `PRUNE_AGE_SECS` are dropped.
+const PRUNE_SIZE: u64 = 256 * 1024;
+const PRUNE_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// One `aittributor mark` call: an agent reporting the files it edited.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Entry {
+    /// The agent's co-author email, as in `Agent::email`.
+    pub agent: String,
+    /// Paths relative to the repository root.
+    pub files: Vec<String>,
+    /// Unix seconds.
+    pub timestamp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// The ledger of the current repository, `<git-dir>/aittributor/ledger.jsonl`.
+pub fn path() -> Option<PathBuf> {
+    git::git_path("aittributor/ledger.jsonl")
+}
+
+/// Read every entry, skipping lines that don't parse (e.g. from a newer version).
+pub fn read(path: &Path) -> Vec<Entry> {
+    fs::read_to_string(path)
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Append an entry, under a lock next to the ledger so a concurrent mark isn't lost when the
+/// ledger is pruned.
+pub fn append(path: &Path, entry: &Entry) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let _lock = Lock::acquire(&path.with_extension("lock"))?;
+    if fs::metadata(path).is_ok_and(|m| m.len() > PRUNE_SIZE) {
+        prune(path, entry.timestamp.saturating_sub(PRUNE_AGE_SECS))?;
+    }
+    let mut line = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+    line.push('\n');
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+fn prune(path: &Path, cutoff: u64) -> std::io::Result<()> {
+    let mut kept = String::new();
+    for entry in read(path).into_iter().filter(|e| e.timestamp >= cutoff) {
+        kept.push_str(&serde_json::to_string(&entry).map_err(std::io::Error::other)?);
+        kept.push('\n');
+    }
+    let tmp = path.with_extension(format!("tmp.{}", std::process::id()));
+    fs::write(&tmp, kept)?;
+    fs::rename(&tmp, path)
+}
+
+/// Dirty files as of each agent's previous turn, for hooks that report turns rather than
+/// files: agent email to each path and its fingerprint.
+type Turns = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Where the state of the working tree at each agent's previous turn is kept.
+fn turns_path() -> Option<PathBuf> {
+    git::git_path("aittributor/turns.json")
+}
+
+/// Size and mtime of a file in the working tree, or "deleted".
+fn fingerprint(path: &Path) -> String {
+    match fs::metadata(path).and_then(|m| Ok((m.len(), m.modified()?))) {
+        Ok((len, modified)) => {
+            let nanos = modified
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or_default();
+            format!("{}:{}", len, nanos)
+        }
+        Err(_) => "deleted".to_string(),
+    }
+}
+
+fn write_turns(path: &Path, turns: &Turns) -> std::io::Result<()> {
+    let tmp = path.with_extension(format!("tmp.{}", std::process::id()));
+    fs::write(&tmp, serde_json::to_string(turns).map_err(std::io::Error::other)?)?;
+    fs::rename(&tmp, path)
+}
+
+/// Uncommitted files that are new or changed since `previous`, the dirty files with their
+/// fingerprints at the last turn.
+fn changed_files_since(previous: Option<&BTreeMap<String, String>>, current: &BTreeMap<String, String>) -> Vec<String> {
+    current
+        .iter()
+        .filter(|(file, print)| previous.is_none_or(|previous| previous.get(*file) != Some(*print)))
+        .map(|(file, _)| file.clone())
+        .collect()
+}
+
+/// Uncommitted files changed since `agent`'s previous turn in this repository, recording the
+/// current state for the next one. The first turn covers everything uncommitted, and files a
+/// person edited between turns are credited to the agent too.
+pub fn changed_since_last_turn(agent: &str, repo_path: &Path) -> Vec<String> {
+    let current: BTreeMap<String, String> = git::changed_files()
+        .into_iter()
+        .map(|file| {
+            let print = fingerprint(&repo_path.join(&file));
+            (file, print)
+        })
+        .collect();
+    let Some(path) = turns_path() else {
+        return current.into_keys().collect();
+    };
+    // Agents finishing turns at once each read and rewrite the state, so one would lose the other's.
+    let _lock = path
+        .parent()
+        .and_then(|dir| fs::create_dir_all(dir).ok())
+        .and_then(|()| Lock::acquire(&path.with_extension("lock")).ok());
+    let mut turns: Turns = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    let changed = changed_files_since(turns.get(agent), &current);
+    turns.insert(agent.to_string(), current);
+    // Losing the state only makes the next turn cover everything uncommitted again.
+    let _ = write_turns(&path, &turns);
+    changed
+}
+
+/// Emails of agents with entries since `since` covering any of the staged files, in the order
+/// they first marked them.
+pub fn agents_covering(entries: &[Entry], staged: &[String], since: u64) -> Vec<String> {
+    let staged: HashSet<&str> = staged.iter().map(String::as_str).collect();
+    let mut agents: Vec<String> = Vec::new();
+    for entry in entries {
+        if entry.timestamp >= since
+            && entry.files.iter().any(|f| staged.contains(f.as_str()))
+            && !agents.contains(&entry.agent)
+        {
+            agents.push(entry.agent.clone());
+        }
+    }
+    agents
+}
+
+/// Agents the ledger credits for the staged changes of the current repository. Entries for
+/// agents no longer known (e.g. removed from the config) are ignored.
+pub fn detect_agents(debug: bool) -> Vec<Candidate> {
+    let Some(path) = path() else {
+        return Vec::new();
+    };
+    let entries = read(&path);
+    if entries.is_empty() {
+        return Vec::new();
+    }
+    let since = git::head_commit_time().unwrap_or(0);
+    let staged = git::staged_files();
+    if debug {
+        eprintln!("\n=== Ledger ===");
+        eprintln!(
+            "  {} entries in {}, {} staged file(s), since {}",
+            entries.len(),
+            path.display(),
+            staged.len(),
+            since
+        );
+    }
+    agents_covering(&entries, &staged, since)
+        .iter()
+        .filter_map(|email| {
+            let agent = Agent::all().find(|a| a.email == email);
+            if debug {
+                match agent {
+                    Some(agent) => eprintln!("  ✓ Ledger credits {}", agent.email),
+                    None => eprintln!("  Unknown agent in ledger: {}", email),
+                }
+            }
+            let latest = entries
+                .iter()
+                .filter(|e| e.agent == *email && e.timestamp >= since)
+                .map(|e| e.timestamp)
+                .max();
+            agent.map(|agent| Candidate {
+                seen: latest,
+                ..Candidate::new(agent, Method::Ledger)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(agent: &str, files: &[&str], timestamp: u64) -> Entry {
+        Entry {
+            agent: agent.to_string(),
+            files: files.iter().map(|f| f.to_string()).collect(),
+            timestamp,
+            session: None,
+        }
+    }
+
+    #[test]
+    fn test_agents_covering_staged_files_since_last_commit() {
+        let entries = [
+            entry("Claude Code <noreply@anthropic.com>", &["src/main.rs"], 100),
+            entry("Codex <noreply@openai.com>", &["README.md"], 200),
+            entry("Amp <amp@ampcode.com>", &["src/lib.rs"], 50),
+            entry("Claude Code <noreply@anthropic.com>", &["src/lib.rs"], 300),
+        ];
+        let staged = ["src/main.rs".to_string(), "src/lib.rs".to_string()];
+        assert_eq!(
+            agents_covering(&entries, &staged, 100),
+            ["Claude Code <noreply@anthropic.com>"]
+        );
+        assert_eq!(
+            agents_covering(&entries, &staged, 0),
+            ["Claude Code <noreply@anthropic.com>", "Amp <amp@ampcode.com>"]
+        );
+        assert!(agents_covering(&entries, &[], 0).is_empty());
+    }
+
+    #[test]
+    fn test_changed_files_since_previous_turn() {
+        let print = |pairs: &[(&str, &str)]| -> BTreeMap<String, String> {
+            pairs.iter().map(|(f, p)| (f.to_string(), p.to_string())).collect()
+        };
+        let previous = print(&[("a.rs", "1:100"), ("b.rs", "2:200")]);
+        let current = print(&[("a.rs", "1:100"), ("b.rs", "3:300"), ("c.rs", "4:400")]);
+        assert_eq!(changed_files_since(Some(&previous), &current), ["b.rs", "c.rs"]);
+        // A question-only turn changes nothing.
+        assert!(changed_files_since(Some(&current), &current).is_empty());
+        assert_eq!(changed_files_since(None, &current), ["a.rs", "b.rs", "c.rs"]);
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("aittributor").join("ledger.jsonl");
+        let mut first = entry("Codex <noreply@openai.com>", &["a.rs"], 10);
+        first.session = Some("s1".to_string());
+        append(&path, &first).unwrap();
+        append(&path, &entry("Amp <amp@ampcode.com>", &["b.rs"], 20)).unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"not json\n")
+            .unwrap();
+
+        let entries = read(&path);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0], first);
+    }
+
+    #[test]
+    fn test_prune_drops_old_entries() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("ledger.jsonl");
+        append(&path, &entry("Codex <noreply@openai.com>", &["a.rs"], 10)).unwrap();
+        append(&path, &entry("Amp <amp@ampcode.com>", &["b.rs"], 20)).unwrap();
+        prune(&path, 15).unwrap();
+        assert_eq!(read(&path), [entry("Amp <amp@ampcode.com>", &["b.rs"], 20)]);
+    }
+}
@@ -0,0 +1,52 @@
+use std::fs;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+
+/// An exclusive `flock` on a lock file, released when dropped.
+pub struct Lock {
+    _file: fs::File,
+}
+
+impl Lock {
+    /// Wait until the lock on `path` is free and take it, creating the file if needed.
+    pub fn acquire(path: &Path) -> std::io::Result<Lock> {
+        Lock::take(path, libc::LOCK_EX)?.ok_or_else(|| std::io::Error::other("flock failed"))
+    }
+
+    /// Take the lock on `path` if no one holds it.
+    pub fn try_acquire(path: &Path) -> std::io::Result<Option<Lock>> {
+        Lock::take(path, libc::LOCK_EX | libc::LOCK_NB)
+    }
+
+    fn take(path: &Path, operation: libc::c_int) -> std::io::Result<Option<Lock>> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+        // SAFETY: the descriptor is owned by `file`, which the returned lock keeps open.
+        if unsafe { libc::flock(file.as_raw_fd(), operation) } != 0 {
+            let error = std::io::Error::last_os_error();
+            return match error.kind() {
+                std::io::ErrorKind::WouldBlock => Ok(None),
+                _ => Err(error),
+            };
+        }
+        Ok(Some(Lock { _file: file }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("x.lock");
+        let held = Lock::acquire(&path).unwrap();
+        assert!(Lock::try_acquire(&path).unwrap().is_none());
+        drop(held);
+        assert!(Lock::try_acquire(&path).unwrap().is_some());
+    }
+}
@@ -1,20 +1,43 @@
+mod activity;
 mod agent;
+mod audit;
 mod breadcrumbs;
+mod cache;
+mod commands;
+mod config;
+mod diff;
 mod git;
+mod ledger;
+mod lock;
+mod notes;
+mod paths;
+mod process;
+mod prompt;
+mod rules;
+mod snapshot;
+mod user;
 
 use clap::Parser;
 use std::path::PathBuf;
 use std::sync::mpsc;
-use std::time::Duration;
-use sysinfo::{Pid, ProcessRefreshKind, RefreshKind, System, UpdateKind};
+use std::time::{Duration, Instant};
 
 use agent::Agent;
-use git::{append_trailers, find_git_root};
+use audit::{Candidate, Method};
+use config::Config;
+use git::{Repo, append_trailers};
+use paths::PathMatcher;
+use process::{Process, ProcessTable};
+use snapshot::Snapshot;
 
 #[derive(Parser)]
 #[command(name = "aittributor", version)]
 #[command(about = "Git prepare-commit-msg hook that adds AI agent attribution")]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<commands::Command>,
+
     /// Path to the commit message file
     commit_msg_file: Option<PathBuf>,
 
@@ -29,25 +52,38 @@ struct Cli {
     /// Enable debug output
     #[arg(long)]
     debug: bool,
+
+    /// Save a redacted snapshot of the process table to FILE, then detect agents from it
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["commit_msg_file", "replay"])]
+    record: Option<PathBuf>,
+
+    /// Detect agents from a snapshot saved with --record instead of the live process table
+    #[arg(long, value_name = "FILE", conflicts_with = "commit_msg_file")]
+    replay: Option<PathBuf>,
+
+    /// Print the trailers that would be added to the commit message file as a diff, leaving
+    /// the file unchanged
+    #[arg(long, requires = "commit_msg_file")]
+    dry_run: bool,
 }
 
-fn walk_ancestry(system: &System, debug: bool) -> Vec<&'static Agent> {
-    let mut current_pid = Pid::from_u32(std::process::id());
+fn walk_ancestry(table: &ProcessTable, start_pid: u32, debug: bool) -> Vec<Candidate> {
+    let mut current_pid = start_pid;
     let mut agents = Vec::new();
 
     if debug {
         eprintln!("\nWalking ancestry from PID {}...", current_pid);
     }
 
-    while let Some(process) = system.process(current_pid) {
+    while let Some(process) = table.process(current_pid) {
         if debug {
-            eprintln!("  PID {}: {:?}", current_pid, process.name());
+            eprintln!("  PID {}: {:?}", current_pid, process.name);
         }
         if let Some(agent) = Agent::find_for_process(process, debug) {
-            agents.push(agent);
+            agents.push(Candidate::process(agent, Method::Ancestry, current_pid));
         }
 
-        match process.parent() {
+        match process.parent {
             Some(parent_pid) if parent_pid != current_pid => {
                 current_pid = parent_pid;
             }
@@ -58,7 +94,31 @@ fn walk_ancestry(system: &System, debug: bool) -> Vec<&'static Agent> {
     agents
 }
 
-fn check_process_tree(system: &System, root_pid: Pid, repo_path: &PathBuf, debug: bool) -> Vec<&'static Agent> {
+/// The directory an agent process works in. Editor plugin helpers take their editor's cwd,
+/// and don't count at all outside one of their host editors.
+fn agent_cwd<'a>(table: &'a ProcessTable, process: &'a Process, agent: &Agent, debug: bool) -> Option<&'a PathBuf> {
+    if agent.host_processes.is_empty() {
+        return process.cwd();
+    }
+    let host = agent.host_process(table, process);
+    if debug {
+        match host {
+            Some(host) => eprintln!("      Hosted by PID {} ({})", host.pid, host.name),
+            None => eprintln!("      No host editor found"),
+        }
+    }
+    host?.cwd()
+}
+
+/// Only processes owned by `owner` are attributed when it is set; ancestry is never filtered
+/// since it is by definition the session running the commit.
+fn check_process_tree(
+    table: &ProcessTable,
+    root_pid: u32,
+    matcher: &PathMatcher,
+    owner: Option<u32>,
+    debug: bool,
+) -> Vec<Candidate> {
     let mut queue = std::collections::VecDeque::new();
     let mut visited = std::collections::HashSet::new();
     let mut agents = Vec::new();
@@ -70,37 +130,44 @@ fn check_process_tree(system: &System, root_pid: Pid, repo_path: &PathBuf, debug
             continue;
         }
 
-        let process = match system.process(pid) {
+        let process = match table.process(pid) {
             Some(p) => p,
             None => continue,
         };
 
         if debug {
-            eprintln!("    Checking PID {}: {:?}", pid, process.name());
+            eprintln!("    Checking PID {}: {:?}", pid, process.name);
         }
 
-        if let Some(agent) = Agent::find_for_process(process, debug)
-            && let Some(cwd) = process.cwd()
-            && cwd.starts_with(repo_path)
+        if Agent::is_candidate(process)
+            && let Some(agent) = Agent::find_for_process(process, debug)
+            && let Some(cwd) = agent_cwd(table, process, agent, debug)
+            && matcher.matches_process(cwd, pid)
         {
-            if debug {
-                eprintln!("    Found agent in tree with matching cwd");
+            if owner.is_none() || process.uid == owner {
+                if debug {
+                    eprintln!("    Found agent in tree with matching cwd");
+                }
+                agents.push(Candidate::process(agent, Method::ProcessTree, pid));
+            } else if debug {
+                eprintln!("    Skipping agent owned by another user (uid {:?})", process.uid);
             }
-            agents.push(agent);
         }
 
-        for child in system.processes().values() {
-            if child.parent() == Some(pid) {
-                queue.push_back(child.pid());
-            }
-        }
+        queue.extend(table.children(pid));
     }
 
     agents
 }
 
-fn walk_ancestry_and_descendants(system: &System, repo_path: &PathBuf, debug: bool) -> Vec<&'static Agent> {
-    let mut current_pid = Pid::from_u32(std::process::id());
+fn walk_ancestry_and_descendants(
+    table: &ProcessTable,
+    start_pid: u32,
+    matcher: &PathMatcher,
+    owner: Option<u32>,
+    debug: bool,
+) -> Vec<Candidate> {
+    let mut current_pid = start_pid;
     let mut checked_ancestors = std::collections::HashSet::new();
     let mut agents = Vec::new();
 
@@ -108,12 +175,12 @@ fn walk_ancestry_and_descendants(system: &System, repo_path: &PathBuf, debug: bo
         eprintln!("\nWalking ancestry and descendants...");
     }
 
-    while let Some(process) = system.process(current_pid) {
+    while let Some(process) = table.process(current_pid) {
         if !checked_ancestors.insert(current_pid) {
             break;
         }
 
-        let parent_pid = match process.parent() {
+        let parent_pid = match process.parent {
             Some(pid) if pid != current_pid => pid,
             _ => break,
         };
@@ -122,12 +189,8 @@ fn walk_ancestry_and_descendants(system: &System, repo_path: &PathBuf, debug: bo
             eprintln!("  Checking siblings of PID {} (parent: {})", current_pid, parent_pid);
         }
 
-        for sibling in system.processes().values() {
-            if sibling.parent() != Some(parent_pid) {
-                continue;
-            }
-
-            agents.extend(check_process_tree(system, sibling.pid(), repo_path, debug));
+        for &sibling in table.children(parent_pid) {
+            agents.extend(check_process_tree(table, sibling, matcher, owner, debug));
         }
 
         current_pid = parent_pid;
@@ -136,7 +199,51 @@ fn walk_ancestry_and_descendants(system: &System, repo_path: &PathBuf, debug: bo
     agents
 }
 
-fn detect_agents(debug: bool) -> Vec<&'static Agent> {
+/// Process-based detection starting from `start_pid`, normally the hook's own process.
+fn detect_in_table(
+    table: &ProcessTable,
+    start_pid: u32,
+    matcher: &PathMatcher,
+    owner: Option<u32>,
+    debug: bool,
+) -> Vec<Candidate> {
+    let mut agents = walk_ancestry(table, start_pid, debug);
+    agents.extend(walk_ancestry_and_descendants(table, start_pid, matcher, owner, debug));
+    agents
+}
+
+/// The repository being committed to, or the current directory outside one.
+fn current_repo_path() -> Option<PathBuf> {
+    match Repo::current() {
+        Some(repo) => Some(repo.work_tree),
+        None => std::env::current_dir().ok(),
+    }
+}
+
+/// Where agents are credited for working: the repository and, as configured, its main worktree
+/// or superproject. Outside a repository, the current directory.
+fn repo_roots(config: &Config) -> Option<Vec<PathBuf>> {
+    match Repo::current() {
+        Some(repo) => Some(repo.roots(config)),
+        None => Some(vec![std::env::current_dir().ok()?]),
+    }
+}
+
+/// Agents found, and whether one of them is making the commit: set in the hook's environment
+/// or found in its ancestry.
+struct Detection {
+    /// Every agent found, once per method that found it.
+    candidates: Vec<Candidate>,
+    agent_initiated: bool,
+}
+
+impl Detection {
+    fn agents(&self) -> Vec<&'static Agent> {
+        dedup_agents(self.candidates.iter().map(|c| c.agent).collect())
+    }
+}
+
+fn detect_agents(config: &Config, debug: bool) -> Detection {
     let mut agents = Vec::new();
 
     if debug {
@@ -147,29 +254,51 @@ fn detect_agents(debug: bool) -> Vec<&'static Agent> {
         if debug {
             eprintln!("  ✓ Found agent via env: {}", agent.email);
         }
-        agents.push(agent);
+        agents.push(Candidate::new(agent, Method::Env));
     }
 
-    let current_dir = match std::env::current_dir() {
-        Ok(d) => d,
-        Err(_) => return agents,
+    let Some(roots) = repo_roots(config) else {
+        let agent_initiated = !agents.is_empty();
+        return Detection {
+            candidates: agents,
+            agent_initiated,
+        };
     };
-    let repo_path = find_git_root(&current_dir).unwrap_or(current_dir);
     if debug {
-        eprintln!("  Repository path: {}", repo_path.display());
-    }
-    let system = System::new_with_specifics(
-        RefreshKind::new().with_processes(
-            ProcessRefreshKind::new()
-                .with_cmd(UpdateKind::Always)
-                .with_cwd(UpdateKind::Always),
-        ),
-    );
+        for root in &roots {
+            eprintln!("  Repository path: {}", root.display());
+        }
+    }
+    let started = Instant::now();
+    let table = ProcessTable::load();
+    if debug {
+        eprintln!(
+            "  Loaded {} processes via {} in {:?}",
+            table.len(),
+            table.backend,
+            started.elapsed()
+        );
+    }
 
-    agents.extend(walk_ancestry(&system, debug));
-    agents.extend(walk_ancestry_and_descendants(&system, &repo_path, debug));
+    let owner = (!config.include_other_users).then(user::current_uid);
+    agents.extend(walk_ancestry(&table, std::process::id(), debug));
+    let agent_initiated = !agents.is_empty();
+    agents.extend(walk_ancestry_and_descendants(
+        &table,
+        std::process::id(),
+        &PathMatcher::new(&roots, &config.path_map).with_mount_info(),
+        owner,
+        debug,
+    ));
 
-    agents
+    if debug {
+        eprintln!("\nProcess detection took {:?}", started.elapsed());
+    }
+
+    Detection {
+        candidates: agents,
+        agent_initiated,
+    }
 }
 
 fn dedup_agents(agents: Vec<&'static Agent>) -> Vec<&'static Agent> {
@@ -183,59 +312,309 @@ fn dedup_agents(agents: Vec<&'static Agent>) -> Vec<&'static Agent> {
         .collect()
 }
 
-fn breadcrumb_fallback(debug: bool) -> Vec<&'static Agent> {
-    let current_dir = std::env::current_dir().unwrap_or_default();
-    let repo_path = find_git_root(&current_dir).unwrap_or(current_dir);
-    breadcrumbs::detect_agents_from_breadcrumbs(&repo_path, debug)
+fn mtime_secs(path: &std::path::Path) -> Option<u64> {
+    let mtime = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(mtime.duration_since(std::time::UNIX_EPOCH).ok()?.as_secs())
 }
 
-fn detect_and_merge(debug: bool) -> Vec<&'static Agent> {
+fn breadcrumb_fallback(config: &Config, debug: bool) -> Vec<Candidate> {
+    repo_roots(config)
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|root| breadcrumbs::detect_breadcrumbs(root, config, debug))
+        .map(|(agent, breadcrumb)| Candidate {
+            seen: breadcrumb.as_deref().and_then(mtime_secs),
+            breadcrumb,
+            ..Candidate::new(agent, Method::Breadcrumb)
+        })
+        .collect()
+}
+
+fn detect_and_merge(config: &Config, debug: bool) -> Detection {
     let (bc_tx, bc_rx) = mpsc::channel();
+    let bc_config = config.clone();
     std::thread::spawn(move || {
-        let _ = bc_tx.send(breadcrumb_fallback(debug));
+        let _ = bc_tx.send(breadcrumb_fallback(&bc_config, debug));
     });
 
-    let mut agents = detect_agents(debug);
+    let mut detection = detect_agents(config, debug);
+    for root in repo_roots(config).unwrap_or_default() {
+        let matcher = PathMatcher::new(std::slice::from_ref(&root), &config.path_map);
+        detection.candidates.extend(activity::detect_agents(&matcher, debug));
+    }
 
     if let Ok(bc_agents) = bc_rx.recv() {
-        agents.extend(bc_agents);
+        detection.candidates.extend(bc_agents);
     }
 
-    dedup_agents(agents)
+    detection
 }
 
-fn run(cli: Cli) {
-    let agents = detect_and_merge(cli.debug);
+fn print_agents(agents: &[&Agent]) {
+    if agents.is_empty() {
+        eprintln!("No agent found");
+        std::process::exit(1);
+    }
+    for agent in agents {
+        println!("{}", agent.email);
+    }
+}
 
-    let Some(commit_msg_file) = cli.commit_msg_file else {
-        if agents.is_empty() {
-            eprintln!("No agent found");
-            std::process::exit(1);
+/// `--record` and `--replay`: process detection against a snapshot. Breadcrumbs are local to
+/// the machine and aren't part of it.
+fn run_snapshot(cli: &Cli) -> Result<(), String> {
+    let config = Config::load();
+    Agent::register(&config.agents);
+    let snapshot = match (&cli.record, &cli.replay) {
+        (Some(path), _) => {
+            let repo_path = current_repo_path().ok_or("cannot determine the current directory")?;
+            let owner = (!config.include_other_users).then(user::current_uid);
+            let snapshot = Snapshot::capture(&ProcessTable::load(), std::process::id(), &repo_path, owner);
+            snapshot.save(path)?;
+            eprintln!("Recorded {} processes to {}", snapshot.processes.len(), path.display());
+            snapshot
         }
-        for agent in &agents {
-            println!("{}", agent.email);
+        (None, Some(path)) => Snapshot::load(path)?,
+        (None, None) => return Ok(()),
+    };
+    let table = snapshot.table();
+    let candidates = detect_in_table(
+        &table,
+        snapshot.start_pid,
+        &PathMatcher::new(std::slice::from_ref(&snapshot.repo_path), &config.path_map),
+        snapshot.owner,
+        cli.debug,
+    );
+    print_agents(&dedup_agents(candidates.iter().map(|c| c.agent).collect()));
+    Ok(())
+}
+
+/// Agents that reported their edits are credited exactly; the heuristics are only for
+/// commits the ledger knows nothing about.
+fn detect(config: &Config, debug: bool) -> Detection {
+    let candidates = ledger::detect_agents(debug);
+    if candidates.is_empty() {
+        return detect_and_merge(config, debug);
+    }
+    // Only the prompt needs to know, so the process table is only loaded for it.
+    let agent_initiated = config.interactive
+        && (Agent::find_by_env().is_some()
+            || !walk_ancestry(&ProcessTable::load(), std::process::id(), debug).is_empty());
+    Detection {
+        candidates,
+        agent_initiated,
+    }
+}
+
+/// How long the hook may take, not counting an interactive prompt. Past it, the commit goes
+/// ahead without attribution.
+const HOOK_BUDGET: Duration = Duration::from_secs(1);
+/// Time left to log a run that overran, so an unwritable state dir can't hang the commit either.
+const TIMEOUT_LOG_BUDGET: Duration = Duration::from_millis(100);
+
+enum Progress<T> {
+    /// The prompt is waiting for an answer, on its own timeout.
+    Paused,
+    Resumed,
+    Done(T),
+}
+
+/// Lets work run by `within_budget` stop the clock while it prompts.
+struct Clock<T>(mpsc::Sender<Progress<T>>);
+
+impl<T> Clock<T> {
+    fn paused<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _ = self.0.send(Progress::Paused);
+        let result = f();
+        let _ = self.0.send(Progress::Resumed);
+        result
+    }
+}
+
+/// Run `work` on another thread, giving up on it after `budget`, not counting time the clock is
+/// paused. An overrunning thread is left behind; the process exits soon after.
+fn within_budget<T: Send + 'static>(budget: Duration, work: impl FnOnce(&Clock<T>) -> T + Send + 'static) -> Option<T> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let clock = Clock(tx);
+        let result = work(&clock);
+        let _ = clock.0.send(Progress::Done(result));
+    });
+    let mut remaining = budget;
+    let mut paused = false;
+    loop {
+        let progress = if paused {
+            rx.recv().ok()
+        } else {
+            let started = Instant::now();
+            let progress = rx.recv_timeout(remaining).ok();
+            remaining = remaining.saturating_sub(started.elapsed());
+            progress
+        };
+        match progress? {
+            Progress::Paused => paused = true,
+            Progress::Resumed => paused = false,
+            Progress::Done(result) => return Some(result),
+        }
+    }
+}
+
+/// Report a run that overran the budget, and log it if it was a hook run.
+fn timed_out(timestamp: u64, commit_source: String, logged: bool, debug: bool) {
+    eprintln!("aittributor: timed out, skipping attribution. Check https://github.com/block/aittributor/issues");
+    if logged {
+        within_budget(TIMEOUT_LOG_BUDGET, move |_: &Clock<()>| {
+            let record = audit::Record {
+                timestamp,
+                repo: current_repo_path().unwrap_or_default(),
+                commit_source,
+                timed_out: true,
+                ..audit::Record::default()
+            };
+            audit::log(&record, debug);
+        });
+    }
+}
+
+/// Detect the agents behind a commit: apply the repository's rule to `config`, then run
+/// detection, filling in `record`. Returns the config as scoped by the rule, or None when the
+/// rule turns attribution off.
+fn detect_for_commit(mut config: Config, record: &mut audit::Record, debug: bool) -> Option<(Config, Detection)> {
+    // Rules ask git about the repository, so that's only done when there are some, and (like
+    // everything else here) within the hook's budget.
+    if !config.rules.is_empty()
+        && let Some(repo) = rules::RepoInfo::current()
+    {
+        let resolution = rules::resolve(&config, &repo);
+        record.rule = resolution.rule.map(|i| i + 1);
+        if !resolution.enabled {
+            if debug {
+                eprintln!(
+                    "Attribution disabled for {} by rule {}",
+                    repo.path.display(),
+                    resolution.rule.map_or(0, |i| i + 1)
+                );
+            }
+            record.disabled = true;
+            return None;
+        }
+        config = resolution.config;
+    }
+
+    let detection = detect(&config, debug);
+    // The same process can be reached from several levels of the ancestry walk.
+    for evidence in detection.candidates.iter().map(audit::Evidence::from) {
+        if !record.candidates.contains(&evidence) {
+            record.candidates.push(evidence);
+        }
+    }
+    Some((config, detection))
+}
+
+/// The agents to credit: those detected, confirmed on the terminal if configured. The prompt
+/// has its own timeout, so the clock is paused while it waits.
+fn credited_agents<T>(config: &Config, detection: &Detection, clock: &Clock<T>) -> Vec<&'static Agent> {
+    let agents = detection.agents();
+    if config.interactive && !detection.agent_initiated && !agents.is_empty() {
+        let timeout = config.interactive_timeout_secs.unwrap_or(prompt::DEFAULT_TIMEOUT_SECS);
+        return clock.paused(|| prompt::confirm(agents, Duration::from_secs(timeout)));
+    }
+    agents
+}
+
+/// Detect, confirm and add the trailers, logging what was done. Run within the hook's budget.
+fn run(cli: Cli, timestamp: u64, clock: &Clock<()>) {
+    let config = Config::load();
+    Agent::register(&config.agents);
+    let mut record = audit::Record {
+        timestamp,
+        repo: current_repo_path().unwrap_or_default(),
+        commit_source: cli.commit_source.clone(),
+        ..audit::Record::default()
+    };
+    // Only hook runs are logged, not `aittributor` run by hand or with --dry-run.
+    let log = |record: &audit::Record| {
+        if cli.commit_msg_file.is_some() && !cli.dry_run {
+            audit::log(record, cli.debug);
         }
+    };
+
+    let Some((config, detection)) = detect_for_commit(config, &mut record, cli.debug) else {
+        log(&record);
+        return;
+    };
+    let Some(commit_msg_file) = &cli.commit_msg_file else {
+        print_agents(&detection.agents());
         return;
     };
 
+    let agents = credited_agents(&config, &detection, clock);
+    if cli.dry_run {
+        if let Err(e) = print_trailer_diff(commit_msg_file, &agents, cli.debug) {
+            eprintln!("aittributor: {}: {}", commit_msg_file.display(), e);
+        }
+        return;
+    }
     for agent in &agents {
-        if let Err(e) = append_trailers(&commit_msg_file, agent, cli.debug) {
-            eprintln!("aittributor: failed to append trailers: {}", e);
+        match append_trailers(commit_msg_file, agent, cli.debug) {
+            Ok(()) => record.trailers.push(agent.email.to_string()),
+            Err(e) => eprintln!("aittributor: failed to append trailers: {}", e),
         }
     }
+    notes::save_pending(&record, cli.debug);
+    log(&record);
 }
 
-fn main() {
-    let cli = Cli::parse();
+/// Show the trailers that would be added to the commit message file, without adding them.
+fn print_trailer_diff(commit_msg_file: &PathBuf, agents: &[&'static Agent], debug: bool) -> std::io::Result<()> {
+    let content = std::fs::read_to_string(commit_msg_file)?;
+    let mut updated = content.clone();
+    for agent in agents {
+        updated = git::add_trailers(&updated, agent, debug)?;
+    }
+    if updated == content {
+        println!("No trailers to add to {}", commit_msg_file.display());
+    } else {
+        println!("--- {}\n+++ {}", commit_msg_file.display(), commit_msg_file.display());
+        print!("{}", diff::diff(&content, &updated));
+    }
+    Ok(())
+}
 
-    let (tx, rx) = mpsc::channel();
-    std::thread::spawn(move || {
-        run(cli);
-        let _ = tx.send(());
-    });
+fn main() {
+    // Linked as the post-commit hook itself, rather than called from one.
+    if std::env::args_os()
+        .next()
+        .is_some_and(|arg0| std::path::Path::new(&arg0).file_name() == Some("post-commit".as_ref()))
+    {
+        if let Err(e) = notes::attach_pending() {
+            eprintln!("aittributor: {}", e);
+        }
+        return;
+    }
+    let mut cli = Cli::parse();
+    if let Some(command) = cli.command.take() {
+        if let Err(e) = commands::run(command) {
+            eprintln!("aittributor: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // Snapshots are for bug reports and run outside the hook's time budget.
+    if cli.record.is_some() || cli.replay.is_some() {
+        if let Err(e) = run_snapshot(&cli) {
+            eprintln!("aittributor: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    if rx.recv_timeout(Duration::from_secs(1)).is_err() {
-        eprintln!("aittributor: timed out, skipping attribution. Check https://github.com/block/aittributor/issues");
+    let timestamp = ledger::now_secs();
+    let commit_source = cli.commit_source.clone();
+    let logged = cli.commit_msg_file.is_some() && !cli.dry_run;
+    let debug = cli.debug;
+    if within_budget(HOOK_BUDGET, move |clock| run(cli, timestamp, clock)).is_none() {
+        timed_out(timestamp, commit_source, logged, debug);
     }
 }
 
@@ -268,6 +647,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_within_budget_excludes_paused_time() {
+        let budget = Duration::from_millis(50);
+        assert_eq!(within_budget(budget, |_: &Clock<u8>| 1), Some(1));
+        let overrun = within_budget(budget, move |_: &Clock<u8>| {
+            std::thread::sleep(budget * 4);
+            1
+        });
+        assert_eq!(overrun, None);
+        let prompted = within_budget(budget, move |clock: &Clock<u8>| {
+            clock.paused(|| std::thread::sleep(budget * 4));
+            2
+        });
+        assert_eq!(prompted, Some(2));
+    }
+
     #[test]
     fn test_dedup_agents_removes_duplicates() {
         let claude = Agent::find_by_name("claude").unwrap();
@@ -316,9 +711,51 @@ mod tests {
         assert!(Agent::find_by_name("/opt/homebrew/bin/amp").is_some());
         assert!(Agent::find_by_name("gemini").is_some());
         assert!(Agent::find_by_name("goose").is_some());
+        // Known, but not credited until the config gives it an email.
+        assert!(Agent::find_by_name("junie").is_none());
+        assert!(Agent::without_email().any(|a| a.name == "junie"));
+        assert_eq!(
+            Agent::find_by_name("copilot-languag").unwrap().email,
+            "GitHub Copilot <noreply@github.com>"
+        );
         assert!(Agent::find_by_name("unknown").is_none());
     }
 
+    #[test]
+    fn test_find_terminal_agents_by_name() {
+        let email = |name| Agent::find_by_name(name).map(|a| a.email);
+        assert_eq!(email("qwen"), Some("Qwen-Coder <qwen-coder@alibabacloud.com>"));
+        assert_eq!(email("crush"), Some("Crush <crush@charm.land>"));
+        assert_eq!(
+            email("/usr/local/bin/droid"),
+            Some("factory-droid[bot] <138933559+factory-droid[bot]@users.noreply.github.com>")
+        );
+        assert_eq!(email("kilocode"), Some("Kilo Code <noreply@kilocode.ai>"));
+        assert_eq!(
+            email("devin"),
+            Some("Devin AI <158243242+devin-ai-integration[bot]@users.noreply.github.com>")
+        );
+        assert_eq!(email("openhands"), Some("openhands <openhands@all-hands.dev>"));
+
+        // No documented co-author address; credited once the config gives one.
+        assert_eq!(email("kiro-cli"), None);
+        assert_eq!(email("auggie"), None);
+        assert_eq!(email("cn"), None);
+        // Only Zed's threads show its agent was used; the editor itself isn't an agent.
+        assert_eq!(email("zed-editor"), None);
+        let unnamed: Vec<_> = Agent::without_email().map(|a| a.name).collect();
+        for name in ["kiro", "auggie", "continue"] {
+            assert!(unnamed.contains(&name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_short_agent_names_match_exactly() {
+        assert!(Agent::find_by_name("android-studio").is_none());
+        assert!(Agent::find_by_name("cnid").is_none());
+        assert!(Agent::find_by_name("zeditor").is_none());
+    }
+
     #[test]
     fn test_find_agent_by_env() {
         unsafe {
@@ -361,7 +798,7 @@ mod tests {
     }
 
     #[test]
-    fn test_find_git_root() {
+    fn test_discover_repo() {
         use std::fs;
         use tempfile::TempDir;
 
@@ -372,11 +809,109 @@ mod tests {
         let subdir = temp_dir.path().join("src").join("deep");
         fs::create_dir_all(&subdir).unwrap();
 
-        let found = find_git_root(&subdir.to_path_buf());
-        assert_eq!(found, Some(temp_dir.path().to_path_buf()));
+        let repo = Repo::discover(&subdir).unwrap();
+        assert_eq!(repo.work_tree, temp_dir.path());
+        assert_eq!(repo.git_dir, git_dir);
+        assert_eq!((repo.main_worktree, repo.superproject), (None, None));
+
+        assert_eq!(Repo::discover(temp_dir.path()).unwrap().work_tree, temp_dir.path());
+    }
+
+    #[test]
+    fn test_discover_linked_worktree() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let main = temp_dir.path().join("main");
+        let linked = temp_dir.path().join("linked");
+        let git_dir = main.join(".git").join("worktrees").join("linked");
+        fs::create_dir_all(&git_dir).unwrap();
+        fs::write(git_dir.join("commondir"), "../..\n").unwrap();
+        fs::create_dir(&linked).unwrap();
+        fs::write(linked.join(".git"), format!("gitdir: {}\n", git_dir.display())).unwrap();
+
+        let repo = Repo::discover(&linked).unwrap();
+        assert_eq!(repo.work_tree, linked);
+        assert_eq!(repo.git_dir, git_dir);
+        assert_eq!(repo.main_worktree, Some(main.clone()));
+        assert_eq!(repo.superproject, None);
+
+        assert_eq!(repo.roots(&Config::default()), std::slice::from_ref(&linked));
+        let config = Config {
+            match_main_worktree: true,
+            ..Config::default()
+        };
+        assert_eq!(repo.roots(&config), [linked, main]);
+    }
+
+    #[test]
+    fn test_discover_linked_worktree_inside_main() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let main = temp_dir.path().to_path_buf();
+        let linked = main.join(".worktrees").join("x");
+        let git_dir = main.join(".git").join("worktrees").join("x");
+        fs::create_dir_all(&git_dir).unwrap();
+        fs::write(git_dir.join("commondir"), "../..\n").unwrap();
+        fs::create_dir_all(&linked).unwrap();
+        fs::write(linked.join(".git"), format!("gitdir: {}\n", git_dir.display())).unwrap();
+
+        let repo = Repo::discover(&linked).unwrap();
+        assert_eq!(repo.main_worktree, Some(main));
+        assert_eq!(repo.superproject, None);
+        let config = Config {
+            match_superproject: true,
+            ..Config::default()
+        };
+        assert_eq!(repo.roots(&config), [linked]);
+    }
+
+    #[test]
+    fn test_discover_submodule() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let superproject = temp_dir.path().to_path_buf();
+        let submodule = superproject.join("vendor").join("lib");
+        fs::create_dir_all(superproject.join(".git").join("modules").join("lib")).unwrap();
+        fs::create_dir_all(&submodule).unwrap();
+        fs::write(submodule.join(".git"), "gitdir: ../../.git/modules/lib\n").unwrap();
+
+        let repo = Repo::discover(&submodule).unwrap();
+        assert_eq!(repo.git_dir, superproject.join(".git").join("modules").join("lib"));
+        assert_eq!(repo.superproject, Some(superproject.clone()));
+        let config = Config {
+            match_superproject: true,
+            ..Config::default()
+        };
+        assert_eq!(repo.roots(&config), [submodule.clone(), superproject.clone()]);
+
+        // A clone nested in another repository is only a submodule if `.gitmodules` says so.
+        let nested = superproject.join("nested");
+        fs::create_dir_all(nested.join(".git")).unwrap();
+        assert_eq!(Repo::discover(&nested).unwrap().superproject, None);
+        fs::write(
+            superproject.join(".gitmodules"),
+            "[submodule \"nested\"]\n\tpath = nested\n",
+        )
+        .unwrap();
+        assert_eq!(Repo::discover(&nested).unwrap().superproject, Some(superproject));
+    }
 
-        let found = find_git_root(&temp_dir.path().to_path_buf());
-        assert_eq!(found, Some(temp_dir.path().to_path_buf()));
+    #[test]
+    fn test_add_trailers_to_message() {
+        let agent = Agent::find_by_name("claude").unwrap();
+        let message = "Fix the parser\n\nHandle empty input.\n";
+        let updated = git::add_trailers(message, agent, false).unwrap();
+        assert_eq!(
+            updated,
+            "Fix the parser\n\nHandle empty input.\n\nCo-authored-by: Claude Code <noreply@anthropic.com>\nAi-assisted: true\n"
+        );
+        assert_eq!(git::add_trailers(&updated, agent, false).unwrap(), updated);
     }
 
     #[test]
@@ -401,4 +936,180 @@ mod tests {
             ai_assisted_count
         );
     }
+
+    fn proc(pid: u32, parent: u32, name: &str, uid: u32, cwd: &str) -> Process {
+        Process::loaded(
+            pid,
+            Some(parent),
+            name.to_string(),
+            Some(uid),
+            vec![name.to_string()],
+            Some(PathBuf::from(cwd)),
+            Vec::new(),
+        )
+    }
+
+    /// shell (10) runs `git commit` (20), which runs the hook (30).
+    fn commit_tree(extra: Vec<Process>) -> ProcessTable {
+        let mut processes = vec![
+            proc(10, 1, "zsh", 1000, "/src/repo"),
+            proc(20, 10, "git", 1000, "/src/repo"),
+            proc(30, 20, "aittributor", 1000, "/src/repo"),
+        ];
+        processes.extend(extra);
+        ProcessTable::new(processes, "test")
+    }
+
+    fn detected(table: &ProcessTable, owner: Option<u32>) -> Vec<&'static str> {
+        let candidates = detect_in_table(
+            table,
+            30,
+            &PathMatcher::new(&[PathBuf::from("/src/repo")], &[]),
+            owner,
+            false,
+        );
+        dedup_agents(candidates.iter().map(|c| c.agent).collect())
+            .iter()
+            .map(|a| a.email)
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_agent_in_ancestry() {
+        let table = ProcessTable::new(
+            vec![
+                proc(5, 1, "claude", 1000, "/elsewhere"),
+                proc(20, 5, "git", 1000, "/src/repo"),
+                proc(30, 20, "aittributor", 1000, "/src/repo"),
+            ],
+            "test",
+        );
+        assert_eq!(detected(&table, Some(1000)), ["Claude Code <noreply@anthropic.com>"]);
+    }
+
+    #[test]
+    fn test_detects_sibling_agent_in_repo_only() {
+        let table = commit_tree(vec![
+            proc(40, 1, "tmux", 1000, "/"),
+            proc(41, 40, "codex", 1000, "/src/repo/app"),
+            proc(42, 40, "goose", 1000, "/src/other"),
+        ]);
+        assert_eq!(detected(&table, Some(1000)), ["Codex <noreply@openai.com>"]);
+    }
+
+    #[test]
+    fn test_detects_agent_named_in_argv_of_launchers() {
+        let launched = |pid, name: &str, cmd: &[&str]| {
+            Process::loaded(
+                pid,
+                Some(1),
+                name.to_string(),
+                Some(1000),
+                cmd.iter().map(|a| a.to_string()).collect(),
+                Some(PathBuf::from("/src/repo")),
+                Vec::new(),
+            )
+        };
+        let table = commit_tree(vec![
+            launched(41, "pnpx", &["pnpx", "--yes", "@openai/codex"]),
+            launched(42, "tsx", &["tsx", "/opt/agents/goose-cli.ts"]),
+            launched(43, "bunx", &["bunx", "crush"]),
+        ]);
+        assert_eq!(
+            detected(&table, Some(1000)),
+            [
+                "Codex <noreply@openai.com>",
+                "Goose <opensource@block.xyz>",
+                "Crush <crush@charm.land>"
+            ]
+        );
+
+        // Argv of anything else isn't read at all.
+        let table = commit_tree(vec![launched(44, "make", &["make", "claude"])]);
+        assert!(detected(&table, Some(1000)).is_empty());
+    }
+
+    #[test]
+    fn test_skips_other_users_agents() {
+        let table = commit_tree(vec![proc(41, 1, "codex", 1001, "/src/repo")]);
+        assert!(detected(&table, Some(1000)).is_empty());
+        assert_eq!(detected(&table, None), ["Codex <noreply@openai.com>"]);
+    }
+
+    #[test]
+    fn test_editor_helper_uses_host_cwd() {
+        let hosted = commit_tree(vec![
+            proc(50, 1, "nvim", 1000, "/src/repo"),
+            proc(51, 50, "node", 1000, "/"),
+            proc(52, 51, "copilot-languag", 1000, "/"),
+        ]);
+        assert_eq!(detected(&hosted, Some(1000)), ["GitHub Copilot <noreply@github.com>"]);
+
+        let unhosted = commit_tree(vec![proc(52, 1, "copilot-languag", 1000, "/src/repo")]);
+        assert!(detected(&unhosted, Some(1000)).is_empty());
+    }
+
+    #[test]
+    fn test_config_defaults_to_own_user_only() {
+        let config: Config = toml::from_str("").unwrap();
+        assert!(!config.include_other_users);
+
+        let config: Config = toml::from_str("include_other_users = true").unwrap();
+        assert!(config.include_other_users);
+    }
+
+    #[test]
+    fn test_config_load_missing_file_is_default() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = Config::load_from(&dir.path().join("config.toml")).unwrap();
+        assert!(!config.include_other_users);
+    }
+
+    #[test]
+    fn test_config_rejects_unknown_keys() {
+        assert!(toml::from_str::<Config>("include_other_userz = true").is_err());
+    }
+
+    #[test]
+    fn test_config_agents() {
+        let config: Config = toml::from_str(
+            r#"
+            [[agents]]
+            email = "Acme Agent <agent@acme.dev>"
+            process_names = ["acme-agent"]
+            env = { ACME_AGENT = "1" }
+            breadcrumb_dir = ".acme/sessions"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.agents.len(), 1);
+        let agent = &config.agents[0];
+        assert_eq!(agent.email, "Acme Agent <agent@acme.dev>");
+        assert_eq!(agent.process_names, ["acme-agent"]);
+        assert_eq!(agent.env.get("ACME_AGENT").map(String::as_str), Some("1"));
+        assert!(!agent.exact_process_match);
+
+        assert!(toml::from_str::<Config>("[[agents]]\nprocess_names = [\"x\"]").is_err());
+    }
+
+    #[test]
+    fn test_config_names_built_in_agent_without_email() {
+        assert!(Agent::all().all(|a| a.name != "avante"));
+        assert!(Agent::without_email().any(|a| a.name == "avante"));
+
+        let config: Config = toml::from_str(
+            r#"
+            [[agents]]
+            name = "avante"
+            email = "Avante <avante@example.com>"
+            "#,
+        )
+        .unwrap();
+        let agent = Agent::from_config(&config.agents[0]);
+        assert_eq!(agent.email, "Avante <avante@example.com>");
+        assert_eq!(
+            agent.breadcrumb_lookup,
+            agent::BreadcrumbLookup::Nvim(agent::NvimHistory::Avante)
+        );
+    }
 }
@@ -0,0 +1,148 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::audit::Record;
+use crate::git::{self, git_stdout};
+use crate::ledger;
+
+/// Where the evidence behind each commit's trailers is kept, as a git note on the commit.
+pub const NOTES_REF: &str = "refs/notes/aittributor";
+
+/// Evidence from prepare-commit-msg, waiting for post-commit to know the commit it went into.
+fn pending_path() -> Option<PathBuf> {
+    git::git_path("aittributor/pending.json")
+}
+
+/// Pending evidence older than this is from a commit that was never made (an aborted commit,
+/// or a `filter` run whose message wasn't committed), and is dropped.
+const PENDING_TTL_SECS: u64 = 10 * 60;
+
+/// The evidence of a hook run, with the HEAD and index tree it saw, which tell whether a
+/// commit is the one the evidence was gathered for.
+#[derive(Debug, Serialize, Deserialize)]
+struct Pending<R = Record> {
+    head: Option<String>,
+    tree: Option<String>,
+    record: R,
+}
+
+impl Pending {
+    /// Whether the commit `head`, with tree `tree`, was made from the state this evidence
+    /// was gathered in: a new commit (or an amended one) of the same tree. A tree that
+    /// couldn't be read, as with conflicts in the index, isn't compared.
+    fn is_for(&self, head: &str, tree: &str) -> bool {
+        self.head.as_deref() != Some(head) && self.tree.as_deref().is_none_or(|t| t == tree)
+    }
+
+    fn is_expired(&self, now: u64) -> bool {
+        now.saturating_sub(self.record.timestamp) > PENDING_TTL_SECS
+    }
+}
+
+/// Keep the evidence of this hook run for the post-commit step. Failing to never affects the
+/// commit.
+pub fn save_pending(record: &Record, debug: bool) {
+    let Some(path) = pending_path() else {
+        return;
+    };
+    let pending = Pending {
+        head: git::rev_parse("HEAD"),
+        tree: git::index_tree(),
+        record,
+    };
+    let result = serde_json::to_string(&pending)
+        .map_err(std::io::Error::other)
+        .and_then(|json| {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, json)
+        });
+    if let Err(e) = result
+        && debug
+    {
+        eprintln!("aittributor: failed to write {}: {}", path.display(), e);
+    }
+}
+
+/// Attach the pending evidence to HEAD, the commit just made. Evidence gathered for another
+/// commit (one that was aborted, or made from a different index) is left alone; it is replaced
+/// by the next run, or dropped once expired.
+pub fn attach_pending() -> Result<(), String> {
+    let path = pending_path().ok_or("not in a git repository")?;
+    let Ok(json) = fs::read_to_string(&path) else {
+        return Ok(());
+    };
+    let pending: Pending = match serde_json::from_str(&json) {
+        Ok(pending) => pending,
+        Err(e) => {
+            let _ = fs::remove_file(&path);
+            return Err(format!("{}: {}", path.display(), e));
+        }
+    };
+    if pending.is_expired(ledger::now_secs()) {
+        let _ = fs::remove_file(&path);
+        return Ok(());
+    }
+    let (Some(head), Some(tree)) = (git::rev_parse("HEAD"), git::rev_parse("HEAD^{tree}")) else {
+        return Ok(());
+    };
+    if !pending.is_for(&head, &tree) {
+        return Ok(());
+    }
+    let _ = fs::remove_file(&path);
+    if pending.record.candidates.is_empty() {
+        return Ok(());
+    }
+    let note = serde_json::to_string(&pending.record).map_err(|e| e.to_string())?;
+    git_stdout(&["notes", "--ref", NOTES_REF, "add", "-f", "-m", &note, "HEAD"])
+        .map(|_| ())
+        .ok_or_else(|| "git notes add failed".to_string())
+}
+
+/// The evidence stored for a commit.
+pub fn load(commit: &str) -> Option<Record> {
+    let json = git_stdout(&["notes", "--ref", NOTES_REF, "show", commit])?;
+    serde_json::from_str(&json).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending(head: Option<&str>, tree: Option<&str>, timestamp: u64) -> Pending {
+        Pending {
+            head: head.map(str::to_string),
+            tree: tree.map(str::to_string),
+            record: Record {
+                timestamp,
+                ..Record::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_pending_matches_new_commit_of_same_tree() {
+        let seen = pending(Some("a1"), Some("t1"), 1000);
+        assert!(seen.is_for("b2", "t1"));
+        // Aborted: HEAD didn't move.
+        assert!(!seen.is_for("a1", "t1"));
+        // Committed from a different index, e.g. a filtered message committed with -a.
+        assert!(!seen.is_for("b2", "t2"));
+
+        // The first commit, and an index with conflicts.
+        assert!(pending(None, Some("t1"), 1000).is_for("b2", "t1"));
+        assert!(pending(Some("a1"), None, 1000).is_for("b2", "t2"));
+    }
+
+    #[test]
+    fn test_pending_expires() {
+        let seen = pending(Some("a1"), Some("t1"), 1000);
+        assert!(!seen.is_expired(1000 + PENDING_TTL_SECS));
+        assert!(seen.is_expired(1001 + PENDING_TTL_SECS));
+        // A clock set back since the hook ran doesn't expire it.
+        assert!(!seen.is_expired(500));
+    }
+}
@@ -0,0 +1,235 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::PathMap;
+use crate::rules::expand_home;
+use crate::user;
+
+/// Decides whether a directory an agent reports working in is inside the repository, when the
+/// two may name it differently: through symlinks (`~/src -> /data/src`), `[[path_map]]`
+/// prefixes, or, for processes in a container, the container's mounts.
+pub struct PathMatcher {
+    /// The repository roots as given, plus their canonical forms.
+    roots: Vec<PathBuf>,
+    /// `[[path_map]]` prefixes: as agents see them, and as git does.
+    maps: Vec<(PathBuf, PathBuf)>,
+    /// Translate process cwds through their mount namespace. Off when matching a snapshot,
+    /// whose PIDs aren't live.
+    mount_info: bool,
+}
+
+impl PathMatcher {
+    pub fn new(roots: &[PathBuf], maps: &[PathMap]) -> Self {
+        let mut all = roots.to_vec();
+        for root in roots {
+            if let Ok(canonical) = fs::canonicalize(root)
+                && !all.contains(&canonical)
+            {
+                all.push(canonical);
+            }
+        }
+        let home = user::home_dir();
+        let expand = |path: &str| PathBuf::from(expand_home(path, home.as_deref()));
+        PathMatcher {
+            roots: all,
+            maps: maps.iter().map(|m| (expand(&m.from), expand(&m.to))).collect(),
+            mount_info: false,
+        }
+    }
+
+    pub fn with_mount_info(self) -> Self {
+        PathMatcher {
+            mount_info: true,
+            ..self
+        }
+    }
+
+    /// The repository roots as agents may name them, for lookups keyed by path.
+    pub fn aliases(&self) -> Vec<PathBuf> {
+        let mut aliases = self.roots.clone();
+        for root in &self.roots {
+            for (from, to) in &self.maps {
+                if let Ok(rest) = root.strip_prefix(to) {
+                    let alias = join(from, rest);
+                    if !aliases.contains(&alias) {
+                        aliases.push(alias);
+                    }
+                }
+            }
+        }
+        aliases
+    }
+
+    /// `path` relative to the root it is under.
+    fn within(&self, path: &Path) -> Option<PathBuf> {
+        self.roots
+            .iter()
+            .find_map(|root| path.strip_prefix(root).ok().map(Path::to_path_buf))
+    }
+
+    /// Where `path`, as an agent reported it, is inside the repository: as written, mapped by
+    /// `[[path_map]]`, or with symlinks resolved, relative to the root it is under.
+    /// Canonicalising touches the filesystem, so it is only tried when nothing else matched.
+    pub fn relative(&self, path: &Path) -> Option<PathBuf> {
+        let mut candidates = vec![path.to_path_buf()];
+        for (from, to) in &self.maps {
+            if let Ok(rest) = path.strip_prefix(from) {
+                candidates.push(join(to, rest));
+            }
+        }
+        candidates
+            .iter()
+            .find_map(|p| self.within(p))
+            .or_else(|| candidates.iter().find_map(|p| self.within(&fs::canonicalize(p).ok()?)))
+    }
+
+    /// Whether `path`, as an agent reported it, is inside the repository.
+    pub fn matches(&self, path: &Path) -> bool {
+        self.relative(path).is_some()
+    }
+
+    /// Like `matches`, for the cwd of process `pid`, which may be in another mount namespace.
+    pub fn matches_process(&self, cwd: &Path, pid: u32) -> bool {
+        if self.matches(cwd) {
+            return true;
+        }
+        if !self.mount_info {
+            return false;
+        }
+        let namespace = |pid: &str| fs::read_link(format!("/proc/{}/ns/mnt", pid)).ok();
+        let theirs = namespace(&pid.to_string());
+        if theirs.is_none() || theirs == namespace("self") {
+            return false;
+        }
+        let mounts =
+            |pid: &str| parse_mountinfo(&fs::read_to_string(format!("/proc/{}/mountinfo", pid)).unwrap_or_default());
+        translate(cwd, &mounts(&pid.to_string()), &mounts("self")).is_some_and(|host| self.matches(&host))
+    }
+}
+
+/// `base` joined with `rest`, without the trailing slash `join` adds for an empty `rest`.
+pub fn join(base: &Path, rest: &Path) -> PathBuf {
+    if rest.as_os_str().is_empty() {
+        base.to_path_buf()
+    } else {
+        base.join(rest)
+    }
+}
+
+/// A line of `/proc/<pid>/mountinfo`: the filesystem's device, the dir within it that is
+/// mounted, and where.
+#[derive(Debug, PartialEq)]
+struct Mount {
+    device: String,
+    root: PathBuf,
+    mount_point: PathBuf,
+}
+
+/// Mountinfo escapes spaces, tabs, newlines and backslashes as octal.
+fn unescape(field: &str) -> String {
+    let mut out = String::new();
+    let mut rest = field;
+    while let Some(i) = rest.find('\\') {
+        out.push_str(&rest[..i]);
+        match rest
+            .get(i + 1..i + 4)
+            .and_then(|octal| u8::from_str_radix(octal, 8).ok())
+        {
+            Some(byte) => {
+                out.push(byte as char);
+                rest = &rest[i + 4..];
+            }
+            None => {
+                out.push('\\');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn parse_mountinfo(content: &str) -> Vec<Mount> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(' ').collect();
+            Some(Mount {
+                device: fields.get(2)?.to_string(),
+                root: PathBuf::from(unescape(fields.get(3)?)),
+                mount_point: PathBuf::from(unescape(fields.get(4)?)),
+            })
+        })
+        .collect()
+}
+
+/// The path in our mount namespace of `path` in another: through the innermost mount holding
+/// it there, to a mount of the same filesystem here.
+fn translate(path: &Path, theirs: &[Mount], ours: &[Mount]) -> Option<PathBuf> {
+    let mount = theirs
+        .iter()
+        .filter(|m| path.starts_with(&m.mount_point))
+        .max_by_key(|m| m.mount_point.components().count())?;
+    let in_fs = join(&mount.root, path.strip_prefix(&mount.mount_point).ok()?);
+    let host = ours
+        .iter()
+        .filter(|m| m.device == mount.device && in_fs.starts_with(&m.root))
+        .max_by_key(|m| m.root.components().count())?;
+    Some(join(&host.mount_point, in_fs.strip_prefix(&host.root).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_mapped_and_symlinked_paths() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let real = dir.path().join("data").join("repo");
+        fs::create_dir_all(real.join("src")).unwrap();
+        let link = dir.path().join("src");
+        std::os::unix::fs::symlink(dir.path().join("data"), &link).unwrap();
+
+        let maps = [PathMap {
+            from: "/workspaces".to_string(),
+            to: link.display().to_string(),
+        }];
+        let matcher = PathMatcher::new(&[link.join("repo")], &maps);
+        assert!(matcher.matches(&link.join("repo").join("src")));
+        assert!(matcher.matches(&real.join("src")));
+        assert!(matcher.matches(Path::new("/workspaces/repo/src")));
+        assert!(!matcher.matches(Path::new("/workspaces/other")));
+        assert!(!matcher.matches(&dir.path().join("data").join("repo2")));
+        assert!(matcher.aliases().contains(&PathBuf::from("/workspaces/repo")));
+
+        // Symlinks in the agent's path are resolved too.
+        let matcher = PathMatcher::new(&[real], &[]);
+        assert!(matcher.matches(&link.join("repo")));
+    }
+
+    #[test]
+    fn test_translate_container_bind_mount() {
+        let container = parse_mountinfo(
+            "620 540 0:52 / / rw,relatime master:1 - overlay overlay rw\n\
+             631 620 259:2 /home/me/src/my\\040repo /workspaces/repo rw,relatime - ext4 /dev/nvme0n1p2 rw\n",
+        );
+        let host = parse_mountinfo(
+            "29 1 259:2 / / rw,relatime shared:1 - ext4 /dev/nvme0n1p2 rw\n\
+             40 29 259:3 / /boot rw,relatime shared:2 - vfat /dev/nvme0n1p1 rw\n",
+        );
+        assert_eq!(
+            container[1],
+            Mount {
+                device: "259:2".to_string(),
+                root: PathBuf::from("/home/me/src/my repo"),
+                mount_point: PathBuf::from("/workspaces/repo"),
+            }
+        );
+        assert_eq!(
+            translate(Path::new("/workspaces/repo/src"), &container, &host),
+            Some(PathBuf::from("/home/me/src/my repo/src"))
+        );
+        // The container's own filesystem isn't mounted on the host.
+        assert_eq!(translate(Path::new("/tmp"), &container, &host), None);
+    }
+}
@@ -0,0 +1,272 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use sysinfo::{ProcessRefreshKind, RefreshKind, System, UpdateKind};
+
+/// A process as seen by detection. Only the pid, parent and name are loaded up front;
+/// cmdline, cwd and environ are read on first access when the table is backed by /proc.
+pub struct Process {
+    pub pid: u32,
+    pub parent: Option<u32>,
+    pub name: String,
+    pub uid: Option<u32>,
+    cmd: OnceCell<Vec<String>>,
+    cwd: OnceCell<Option<PathBuf>>,
+    environ: OnceCell<Vec<(String, String)>>,
+    lazy: bool,
+}
+
+impl Process {
+    /// A process with every field already known, as from sysinfo or a snapshot.
+    pub fn loaded(
+        pid: u32,
+        parent: Option<u32>,
+        name: String,
+        uid: Option<u32>,
+        cmd: Vec<String>,
+        cwd: Option<PathBuf>,
+        environ: Vec<(String, String)>,
+    ) -> Self {
+        Process {
+            pid,
+            parent,
+            name,
+            uid,
+            cmd: OnceCell::from(cmd),
+            cwd: OnceCell::from(cwd),
+            environ: OnceCell::from(environ),
+            lazy: false,
+        }
+    }
+
+    pub fn cmd(&self) -> &[String] {
+        self.cmd.get_or_init(|| {
+            if self.lazy {
+                procfs::read_cmdline(self.pid)
+            } else {
+                Vec::new()
+            }
+        })
+    }
+
+    pub fn cwd(&self) -> Option<&PathBuf> {
+        self.cwd
+            .get_or_init(|| if self.lazy { procfs::read_cwd(self.pid) } else { None })
+            .as_ref()
+    }
+
+    pub fn environ(&self) -> &[(String, String)] {
+        self.environ.get_or_init(|| {
+            if self.lazy {
+                procfs::read_environ(self.pid)
+            } else {
+                Vec::new()
+            }
+        })
+    }
+}
+
+pub struct ProcessTable {
+    processes: HashMap<u32, Process>,
+    children: HashMap<u32, Vec<u32>>,
+    pub backend: &'static str,
+}
+
+impl ProcessTable {
+    /// Load the process table from /proc where available, falling back to sysinfo.
+    pub fn load() -> Self {
+        if cfg!(target_os = "linux")
+            && let Some(processes) = procfs::list_processes()
+        {
+            return Self::new(processes, "procfs");
+        }
+        Self::from_sysinfo()
+    }
+
+    fn from_sysinfo() -> Self {
+        let system = System::new_with_specifics(
+            RefreshKind::new().with_processes(
+                ProcessRefreshKind::new()
+                    .with_cmd(UpdateKind::Always)
+                    .with_cwd(UpdateKind::Always)
+                    .with_user(UpdateKind::Always),
+            ),
+        );
+        let processes = system
+            .processes()
+            .values()
+            .map(|p| {
+                Process::loaded(
+                    p.pid().as_u32(),
+                    p.parent().map(|pid| pid.as_u32()),
+                    p.name().to_string_lossy().into_owned(),
+                    p.user_id().map(|uid| **uid),
+                    p.cmd().iter().map(|s| s.to_string_lossy().into_owned()).collect(),
+                    p.cwd().map(|c| c.to_path_buf()),
+                    // Only the hook's own environment is checked for agents.
+                    Vec::new(),
+                )
+            })
+            .collect();
+        Self::new(processes, "sysinfo")
+    }
+
+    pub fn new(processes: Vec<Process>, backend: &'static str) -> Self {
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        for process in &processes {
+            if let Some(parent) = process.parent {
+                children.entry(parent).or_default().push(process.pid);
+            }
+        }
+        ProcessTable {
+            processes: processes.into_iter().map(|p| (p.pid, p)).collect(),
+            children,
+            backend,
+        }
+    }
+
+    pub fn process(&self, pid: u32) -> Option<&Process> {
+        self.processes.get(&pid)
+    }
+
+    pub fn children(&self, pid: u32) -> &[u32] {
+        self.children.get(&pid).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.processes.len()
+    }
+
+    /// All processes, ordered by pid.
+    pub fn iter(&self) -> impl Iterator<Item = &Process> {
+        let mut processes: Vec<&Process> = self.processes.values().collect();
+        processes.sort_by_key(|p| p.pid);
+        processes.into_iter()
+    }
+}
+
+fn split_env(entry: &str) -> Option<(String, String)> {
+    let (key, value) = entry.split_once('=')?;
+    Some((key.to_string(), value.to_string()))
+}
+
+/// Minimal /proc reader. Listing only touches /proc/<pid>/stat; everything else is read on demand.
+mod procfs {
+    use std::cell::OnceCell;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use super::{Process, split_env};
+    use crate::user::owner_of;
+
+    pub fn list_processes() -> Option<Vec<Process>> {
+        let entries = fs::read_dir("/proc").ok()?;
+        let processes = entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+            .filter_map(|pid| {
+                let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+                let (name, parent) = parse_stat(&stat)?;
+                Some(Process {
+                    pid,
+                    parent,
+                    name,
+                    uid: owner_of(Path::new(&format!("/proc/{}", pid))),
+                    cmd: OnceCell::new(),
+                    cwd: OnceCell::new(),
+                    environ: OnceCell::new(),
+                    lazy: true,
+                })
+            })
+            .collect::<Vec<_>>();
+        if processes.is_empty() { None } else { Some(processes) }
+    }
+
+    /// Parse the comm and ppid out of /proc/<pid>/stat. The comm is wrapped in parentheses
+    /// and may itself contain spaces or parentheses, so split on the last ')'.
+    pub fn parse_stat(stat: &str) -> Option<(String, Option<u32>)> {
+        let open = stat.find('(')?;
+        let close = stat.rfind(')')?;
+        let name = stat.get(open + 1..close)?.to_string();
+        let mut fields = stat.get(close + 1..)?.split_whitespace();
+        let _state = fields.next()?;
+        let ppid = fields.next()?.parse::<u32>().ok()?;
+        Some((name, if ppid == 0 { None } else { Some(ppid) }))
+    }
+
+    pub fn parse_nul_separated(bytes: &[u8]) -> Vec<String> {
+        bytes
+            .split(|&b| b == 0)
+            .filter(|s| !s.is_empty())
+            .map(|s| String::from_utf8_lossy(s).into_owned())
+            .collect()
+    }
+
+    pub fn read_cmdline(pid: u32) -> Vec<String> {
+        fs::read(format!("/proc/{}/cmdline", pid))
+            .map(|bytes| parse_nul_separated(&bytes))
+            .unwrap_or_default()
+    }
+
+    pub fn read_cwd(pid: u32) -> Option<PathBuf> {
+        fs::read_link(format!("/proc/{}/cwd", pid)).ok()
+    }
+
+    pub fn read_environ(pid: u32) -> Vec<(String, String)> {
+        fs::read(format!("/proc/{}/environ", pid))
+            .map(|bytes| {
+                parse_nul_separated(&bytes)
+                    .iter()
+                    .filter_map(|e| split_env(e))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stat() {
+        let stat = "1234 (claude) S 1200 1234 1200 34816 1234 4194560 0 0";
+        assert_eq!(procfs::parse_stat(stat), Some(("claude".to_string(), Some(1200))));
+    }
+
+    #[test]
+    fn test_parse_stat_name_with_parens_and_spaces() {
+        let stat = "42 (tmux: server) (x) S 1 42 42 0 -1";
+        assert_eq!(
+            procfs::parse_stat(stat),
+            Some(("tmux: server) (x".to_string(), Some(1)))
+        );
+    }
+
+    #[test]
+    fn test_parse_stat_init_has_no_parent() {
+        let stat = "1 (systemd) S 0 1 1 0 -1";
+        assert_eq!(procfs::parse_stat(stat), Some(("systemd".to_string(), None)));
+    }
+
+    #[test]
+    fn test_parse_nul_separated() {
+        let bytes = b"node\0/usr/local/bin/claude\0--resume\0";
+        assert_eq!(
+            procfs::parse_nul_separated(bytes),
+            vec!["node", "/usr/local/bin/claude", "--resume"]
+        );
+    }
+
+    #[test]
+    fn test_load_finds_current_process_and_parent() {
+        let table = ProcessTable::load();
+        let process = table.process(std::process::id()).unwrap();
+        assert!(!process.name.is_empty());
+        assert_eq!(process.uid, Some(crate::user::current_uid()));
+        assert!(process.cwd().is_some());
+        let parent = process.parent.unwrap();
+        assert!(table.children(parent).contains(&process.pid));
+    }
+}
@@ -0,0 +1,118 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::agent::Agent;
+
+/// How long the prompt waits when the config doesn't say.
+pub const DEFAULT_TIMEOUT_SECS: u64 = 15;
+
+/// Apply an answer to the prompt: nothing keeps the agents, `n` drops them all, numbers drop
+/// the agents listed under them, and `+name` adds an agent by name or email.
+fn apply_answer(agents: &[&'static Agent], answer: &str) -> Result<Vec<&'static Agent>, String> {
+    let answer = answer.trim();
+    if answer.eq_ignore_ascii_case("n") || answer.eq_ignore_ascii_case("none") {
+        return Ok(Vec::new());
+    }
+    let mut dropped = Vec::new();
+    let mut added = Vec::new();
+    for token in answer
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+    {
+        if let Some(name) = token.strip_prefix('+') {
+            added.push(Agent::find_by_name_or_email(name).ok_or_else(|| format!("unknown agent: {}", name))?);
+        } else {
+            match token.parse::<usize>() {
+                Ok(n) if (1..=agents.len()).contains(&n) => dropped.push(n - 1),
+                _ => return Err(format!("not an agent number: {}", token)),
+            }
+        }
+    }
+    let mut kept: Vec<&'static Agent> = agents
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !dropped.contains(i))
+        .map(|(_, a)| *a)
+        .collect();
+    for agent in added {
+        if !kept.iter().any(|k| std::ptr::eq(*k, agent)) {
+            kept.push(agent);
+        }
+    }
+    Ok(kept)
+}
+
+/// Ask on the terminal which agents to credit. Without a terminal, or when no answer comes
+/// within `timeout`, the detected agents are kept.
+pub fn confirm(agents: Vec<&'static Agent>, timeout: Duration) -> Vec<&'static Agent> {
+    let Ok(mut tty) = File::options().read(true).write(true).open("/dev/tty") else {
+        return agents;
+    };
+    let Ok(input) = tty.try_clone() else {
+        return agents;
+    };
+    // The reader is left blocked if the prompt times out; the process exits soon after.
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(input).lines() {
+            if line.is_err() || tx.send(line.unwrap_or_default()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let _ = writeln!(tty, "aittributor: crediting these agents as co-authors:");
+    for (i, agent) in agents.iter().enumerate() {
+        let _ = writeln!(tty, "  {}. {}", i + 1, agent.email);
+    }
+    let deadline = Instant::now() + timeout;
+    loop {
+        let _ = write!(
+            tty,
+            "Enter to accept, numbers to drop, n to drop all, +name to add [{}s]: ",
+            timeout.as_secs()
+        );
+        let _ = tty.flush();
+        match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(answer) => match apply_answer(&agents, &answer) {
+                Ok(chosen) => return chosen,
+                Err(e) => {
+                    let _ = writeln!(tty, "{}", e);
+                }
+            },
+            Err(_) => {
+                let _ = writeln!(tty, "\nNo answer, keeping the detected agents");
+                return agents;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_answer() {
+        let claude = Agent::find_by_name("claude").unwrap();
+        let codex = Agent::find_by_name("codex").unwrap();
+        let amp = Agent::find_by_name("amp").unwrap();
+        let emails = |agents: Vec<&Agent>| agents.iter().map(|a| a.email).collect::<Vec<_>>();
+        let detected = [claude, codex];
+
+        assert_eq!(
+            emails(apply_answer(&detected, "\n").unwrap()),
+            [claude.email, codex.email]
+        );
+        assert!(apply_answer(&detected, "n").unwrap().is_empty());
+        assert_eq!(emails(apply_answer(&detected, "1").unwrap()), [codex.email]);
+        assert_eq!(
+            emails(apply_answer(&detected, "2, +amp +claude").unwrap()),
+            [claude.email, amp.email]
+        );
+        assert!(apply_answer(&detected, "3").is_err());
+        assert!(apply_answer(&detected, "+nonexistent-agent").is_err());
+    }
+}
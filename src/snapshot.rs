@@ -0,0 +1,208 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::Agent;
+use crate::process::{Process, ProcessTable};
+
+/// Replaces everything the snapshot doesn't need to keep.
+const REDACTED: &str = "<redacted>";
+
+/// A process table captured with `--record`, replayed with `--replay` to reproduce detection
+/// on another machine or in tests.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+    /// The process detection starts from, the hook itself when recorded.
+    pub start_pid: u32,
+    pub repo_path: PathBuf,
+    /// Uid whose processes are attributed, unless other users are included.
+    pub owner: Option<u32>,
+    pub processes: Vec<ProcessRecord>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProcessRecord {
+    pub pid: u32,
+    pub parent: Option<u32>,
+    pub name: String,
+    pub uid: Option<u32>,
+    #[serde(default)]
+    pub cmd: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    /// Only the variables agents are identified by.
+    #[serde(default)]
+    pub environ: Vec<(String, String)>,
+}
+
+/// Keep what detection reads of argv: the basename of argv[0], and the first argument after
+/// it that isn't a flag, but only as the name of the agent it names (`node .../claude`), since
+/// it may as well be a prompt (`claude -p "..."`). Flags before it are kept without their
+/// `=value`s; everything after is dropped.
+fn redact_cmd(cmd: &[String]) -> Vec<String> {
+    let Some((arg0, args)) = cmd.split_first() else {
+        return Vec::new();
+    };
+    let basename = Path::new(arg0).file_name().and_then(|n| n.to_str()).unwrap_or(arg0);
+    let mut kept = vec![basename.to_string()];
+    for arg in args {
+        if arg.starts_with('-') {
+            match arg.split_once('=') {
+                Some((flag, _)) => kept.push(format!("{}={}", flag, REDACTED)),
+                None => kept.push(arg.clone()),
+            }
+            continue;
+        }
+        let agent = Agent::find_by_name(arg).and_then(|agent| agent.process_names.first());
+        kept.push(agent.map_or(REDACTED, |name| *name).to_string());
+        break;
+    }
+    kept
+}
+
+/// Replace the home directory prefix with `~`, consistently across cwds and the repo path.
+fn redact_path(path: &Path, home: Option<&Path>) -> PathBuf {
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rest) => Path::new("~").join(rest),
+        None => path.to_path_buf(),
+    }
+}
+
+impl Snapshot {
+    pub fn capture(table: &ProcessTable, start_pid: u32, repo_path: &Path, owner: Option<u32>) -> Self {
+        let home = crate::user::home_dir();
+        let home = home.as_deref();
+        let signature_vars: Vec<&str> = Agent::all()
+            .flat_map(|agent| agent.env_vars.iter().map(|(key, _)| *key))
+            .collect();
+        let processes = table
+            .iter()
+            .map(|p| ProcessRecord {
+                pid: p.pid,
+                parent: p.parent,
+                name: p.name.clone(),
+                uid: p.uid,
+                // Argv is only read for candidates; everything else keeps just the basics.
+                cmd: if Agent::is_candidate(p) {
+                    redact_cmd(p.cmd())
+                } else {
+                    Vec::new()
+                },
+                cwd: p.cwd().map(|cwd| redact_path(cwd, home)),
+                environ: p
+                    .environ()
+                    .iter()
+                    .filter(|(key, _)| signature_vars.contains(&key.as_str()))
+                    .cloned()
+                    .collect(),
+            })
+            .collect();
+        Snapshot {
+            start_pid,
+            repo_path: redact_path(repo_path, home),
+            owner,
+            processes,
+        }
+    }
+
+    pub fn table(&self) -> ProcessTable {
+        let processes = self
+            .processes
+            .iter()
+            .map(|p| {
+                Process::loaded(
+                    p.pid,
+                    p.parent,
+                    p.name.clone(),
+                    p.uid,
+                    p.cmd.clone(),
+                    p.cwd.clone(),
+                    p.environ.clone(),
+                )
+            })
+            .collect();
+        ProcessTable::new(processes, "snapshot")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json + "\n").map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        serde_json::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_redact_cmd_keeps_what_detection_reads() {
+        assert_eq!(
+            redact_cmd(&args(&[
+                "node",
+                "--max-old-space-size=4096",
+                "/usr/local/bin/claude",
+                "-p",
+                "the secret prompt"
+            ])),
+            args(&["node", "--max-old-space-size=<redacted>", "claude"])
+        );
+        assert_eq!(
+            redact_cmd(&args(&["/home/alice/.local/bin/claude", "-p", "the secret prompt"])),
+            args(&["claude", "-p", "<redacted>"])
+        );
+        assert_eq!(
+            redact_cmd(&args(&["python3", "/home/alice/scripts/deploy.py", "--token=abc"])),
+            args(&["python3", "<redacted>"])
+        );
+        assert_eq!(redact_cmd(&args(&["codex"])), args(&["codex"]));
+    }
+
+    #[test]
+    fn test_redact_path() {
+        let home = Path::new("/home/alice");
+        assert_eq!(
+            redact_path(Path::new("/home/alice/src/repo"), Some(home)),
+            PathBuf::from("~/src/repo")
+        );
+        assert_eq!(
+            redact_path(Path::new("/srv/repo"), Some(home)),
+            PathBuf::from("/srv/repo")
+        );
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let snapshot: Snapshot = serde_json::from_str(
+            r#"{
+                "start_pid": 30,
+                "repo_path": "~/src/repo",
+                "owner": 1000,
+                "processes": [
+                    {"pid": 1, "parent": null, "name": "init", "uid": 0, "cwd": "/"},
+                    {"pid": 30, "parent": 1, "name": "aittributor", "uid": 1000, "cwd": "~/src/repo",
+                     "environ": [["CLINE_ACTIVE", "true"]]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let table = snapshot.table();
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.backend, "snapshot");
+        assert_eq!(table.children(1), &[30]);
+        assert_eq!(table.process(30).unwrap().environ()[0].0, "CLINE_ACTIVE");
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("snapshot.json");
+        snapshot.save(&path).unwrap();
+        assert_eq!(Snapshot::load(&path).unwrap().processes.len(), 2);
+    }
+}
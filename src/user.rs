@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub fn current_uid() -> u32 {
+    // SAFETY: getuid has no preconditions and cannot fail.
+    unsafe { libc::getuid() }
+}
+
+/// Owner uid of a path, or None if it can't be stat'ed.
+pub fn owner_of(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|m| m.uid())
+}
+
+/// Home directory of the invoking user. `$HOME` is used when it belongs to that user;
+/// otherwise (e.g. `sudo` keeping the caller's environment, or a shared service account
+/// home) the account from `whoami` is looked up in /etc/passwd.
+pub fn home_dir() -> Option<PathBuf> {
+    let env_home = std::env::var_os("HOME").filter(|h| !h.is_empty()).map(PathBuf::from);
+    if let Some(home) = &env_home
+        && owner_of(home) == Some(current_uid())
+    {
+        return env_home;
+    }
+    whoami::username()
+        .ok()
+        .and_then(|name| passwd_home(&fs::read_to_string("/etc/passwd").ok()?, &name))
+        .or(env_home)
+}
+
+/// An XDG base directory: `$var` when set, otherwise `default` under the home directory.
+pub fn xdg_dir(var: &str, default: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|h| h.join(default)))
+}
+
+fn passwd_home(passwd: &str, username: &str) -> Option<PathBuf> {
+    passwd.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields.len() >= 6 && fields[0] == username && !fields[5].is_empty() {
+            Some(PathBuf::from(fields[5]))
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passwd_home() {
+        let passwd = "root:x:0:0:root:/root:/bin/bash\nalice:x:1000:1000:Alice,,,:/home/alice:/bin/zsh\n";
+        assert_eq!(passwd_home(passwd, "alice"), Some(PathBuf::from("/home/alice")));
+        assert_eq!(passwd_home(passwd, "root"), Some(PathBuf::from("/root")));
+        assert_eq!(passwd_home(passwd, "bob"), None);
+    }
+
+    #[test]
+    fn test_owner_of_own_tempdir() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(owner_of(dir.path()), Some(current_uid()));
+    }
+}